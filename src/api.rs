@@ -1,221 +1,1235 @@
-use std::collections::HashMap;
+use std::io::{self, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::process::Command;
+use std::{env, fs};
+use std::thread;
+
+use serde_json::json;
 
 use crate::args::*;
-use crate::config::Cfg;
-use crate::utils::display::{fmt_task, fmt_time, HOURGLASS};
-use crate::utils::request::{make_get_request, make_post_request};
-use crate::utils::{calculate_time, Task, TimeEntry};
-use chrono::{Datelike, Days, Local, Timelike};
-pub fn time_get(arg: TimeGet, cfg: &Cfg) -> Result<String, reqwest::Error> {
-    let url = format!(
-        "https://api.clickup.com/api/v2/team/{}/time_entries",
-        cfg.team_id
-    );
-    match arg {
-        TimeGet::Today => {
-            let local = Local::now().date_naive();
-            let start = local.and_hms_opt(0, 0, 1).unwrap().timestamp_millis();
-            let end: i64 = Local::now().timestamp_millis();
-            let res = make_get_request(&cfg, start, end, url);
-            match res {
-                Ok(res) => {
-                    let res = calculate_time(res);
-                    Ok(format!(
-                        "{} Tracked time today: {} out of {}",
-                        HOURGLASS,
-                        fmt_time(res),
-                        fmt_time(cfg.daily_quota)
-                    ))
-                }
-                Err(e) => Err(e),
-            }
+use crate::display::{fmt_balance, fmt_comment, fmt_task, fmt_task_line, fmt_time, ERROR, HOURGLASS, PALM_TREE, STOPWATCH, TAG, TOMATO};
+use crate::prompt::{ask, ask_index};
+use chrono::{DateTime, Datelike, Days, Duration, DurationRound, Months, NaiveDate, Utc};
+use cupcli::cache::{self, CacheKind, CacheMode};
+use cupcli::calendar;
+use cupcli::check::{check, gaps_between, Issue};
+use cupcli::clock::Range;
+use cupcli::git;
+use cupcli::ledger::{self, Period};
+use cupcli::pomodoro::{self, Phase, Pomodoro};
+use cupcli::project::{self, GitHooks};
+use cupcli::remind;
+use cupcli::journal::{self, QueuedEntry, SyncOptions, SyncReport};
+use cupcli::search::rank;
+use cupcli::utils::calculate_time_in;
+use cupcli::models::{hours, NewTimeEntry, StartTimer, Tag, TagRename, TimeEntryUpdate};
+use cupcli::{Cfg, Client, Error, Status, Task, TimeEntry};
+
+pub fn time_get(arg: TimeGet, cfg: &Cfg) -> Result<String, Error> {
+    let today = cfg.zone.today();
+    // the days of the report, both inclusive
+    let (first, last, label) = match arg {
+        TimeGet::Today => (today, today, "today"),
+        TimeGet::Yesterday => {
+            let yesterday = today - Days::new(1);
+            (yesterday, yesterday, "yesterday")
         }
         TimeGet::Week => {
-            let now = Local::now();
-            let closest_past_monday = now
-                .checked_sub_days(Days::new(now.weekday().num_days_from_monday().into()))
-                .unwrap();
-            let start = closest_past_monday
-                .date_naive()
-                .and_hms_opt(0, 0, 1)
-                .unwrap()
-                .timestamp_millis();
-            let end = now.timestamp_millis();
-            let res = make_get_request(&cfg, start, end, url);
-            match res {
-                Ok(res) => {
-                    let res = calculate_time(res);
-                    Ok(format!(
-                        "{} Tracked time this week: {} out of {}",
-                        HOURGLASS,
-                        fmt_time(res),
-                        fmt_time(cfg.daily_quota * 5f32)
-                    ))
-                }
-                Err(e) => Err(e),
-            }
+            let monday = ledger::week_of(today);
+            (monday, monday + Days::new(6), "this week")
         }
-        TimeGet::Yesterday => {
-            let now = Local::now();
-            let yesterday = now.checked_sub_days(Days::new(1)).unwrap();
-            let start = yesterday
-                .date_naive()
-                .and_hms_opt(0, 0, 1)
-                .unwrap()
-                .timestamp_millis();
-            let end = yesterday
-                .date_naive()
-                .and_hms_opt(23, 59, 59)
-                .unwrap()
-                .timestamp_millis();
-            let res = make_get_request(&cfg, start, end, url);
-            match res {
-                Ok(res) => {
-                    let res = calculate_time(res);
-                    Ok(format!(
-                        "{} Tracked time yesterday: {} out of {}",
-                        HOURGLASS,
-                        fmt_time(res),
-                        fmt_time(cfg.daily_quota)
-                    ))
-                }
-                Err(e) => Err(e),
-            }
+        TimeGet::Month => {
+            let first = ledger::month_of(today);
+            (first, first.checked_add_months(Months::new(1)).unwrap() - Days::new(1), "this month")
         }
+    };
+    let range = Range::days(cfg.zone, first, last);
+    let entries = Client::new(cfg).time_entries_overlapping(&range)?;
+    let day_off = if first == last { fmt_day_off(cfg, first) } else { String::new() };
+    Ok(format!(
+        "{} Tracked time {}: {} out of {}{}",
+        HOURGLASS,
+        label,
+        fmt_time(calculate_time_in(&entries, &range)),
+        fmt_time(cfg.quota_between(first, last)),
+        day_off
+    ))
+}
+
+fn fmt_day_off(cfg: &Cfg, day: NaiveDate) -> String {
+    match cfg.days_off.holiday(day) {
+        Some(name) => format!(" ({})", name),
+        None if cfg.days_off.is_vacation(day) => " (vacation)".to_string(),
+        None => String::new(),
     }
 }
 
-pub fn task_get(arg: TaskGet, cfg: &Cfg) -> Result<String, reqwest::Error> {
-    let url = format!(
-        "https://api.clickup.com/api/v2/team/{}/time_entries",
-        cfg.team_id
-    );
-    let now = Local::now();
+pub fn task_get(arg: TaskGet, cfg: &Cfg) -> Result<String, Error> {
     match arg {
         TaskGet::Last => {
-            let start_ndt = now
-                .checked_sub_days(Days::new(cfg.look_behind))
-                .unwrap()
-                .date_naive()
-                .and_hms_opt(0, 0, 1)
-                .unwrap();
-            let start_ts = start_ndt.timestamp_millis();
-            let end = now.timestamp_millis();
-            let res = make_get_request(&cfg, start_ts, end, url);
-            match res {
-                Ok(res) => {
-                    if let Some(last_entry) = res.data.last() {
-                        Ok(fmt_task(&last_entry))
-                    } else {
-                        Ok(format!(
-                            "No tasks tracked since {}",
-                            start_ndt.format("%d/%m/%Y %H:%M:%S")
-                        ))
-                    }
-                }
-                Err(e) => Err(e),
+            match task_get_last_internal(cfg)? {
+                Some(last_entry) => Ok(fmt_task(&last_entry)),
+                None => Ok(format!(
+                    "No tasks tracked since {}",
+                    cfg.zone.naive(look_behind_start(cfg)).format("%d/%m/%Y %H:%M:%S")
+                )),
+            }
+        }
+        TaskGet::Sprint => {
+            // the configured list is the current sprint, show what's assigned to us in it
+            let assignee = Some(cfg.user_id.as_str()).filter(|id| !id.is_empty());
+            let tasks = Client::new(cfg).list_tasks(&cfg.list_id, assignee)?;
+            if tasks.is_empty() {
+                return Ok("No tasks assigned to you in the current sprint".to_string());
             }
+            Ok(tasks.iter().map(fmt_task_line).collect::<Vec<String>>().join("\n"))
         }
-        TaskGet::Sprint => todo!(),
     }
 }
 
-// Gets the last time entry without handling the response
-fn task_get_last_internal(cfg: &Cfg) -> Result<TimeEntry, reqwest::Error> {
-    let url = format!(
-        "https://api.clickup.com/api/v2/team/{}/time_entries",
-        cfg.team_id
-    );
-    let now = Local::now();
-    let start_ndt = now
-        .checked_sub_days(Days::new(cfg.look_behind))
-        .unwrap()
-        .date_naive()
-        .and_hms_opt(0, 0, 1)
-        .unwrap();
-    let start_ts = start_ndt.timestamp_millis();
-    let end = now.timestamp_millis();
-    let res = make_get_request(&cfg, start_ts, end, url)?
-        .data
-        .into_iter()
-        .last()
-        .unwrap(); // unsafe unwrap
-    Ok(res)
-}
-
-#[allow(dead_code)]
-pub fn time_track(args: TimeTrack, cfg: &Cfg) -> Result<String, reqwest::Error> {
-    let mut body = HashMap::with_capacity(10);
-    let end = Local::now().with_second(0).unwrap().timestamp_millis();
-    let time_entry: TimeEntry;
-    let task: Task;
-    let mut duration: i64 = 0;
+fn look_behind_start(cfg: &Cfg) -> DateTime<Utc> {
+    cfg.zone.start_of(cfg.zone.today() - Days::new(cfg.look_behind))
+}
+
+// Gets the last time entry without handling the response, served from the cache when fresh
+fn task_get_last_internal(cfg: &Cfg) -> Result<Option<TimeEntry>, Error> {
+    cache::last_entry(cfg, look_behind_start(cfg))
+}
+
+// posts a time entry, or writes it to the offline journal if we are offline or the request didn't go through
+fn book(cfg: &Cfg, body: NewTimeEntry, label: String, offline: bool) -> Result<String, Error> {
+    let duration = hours(body.duration);
+    let failure = if offline {
+        None
+    } else {
+        match Client::new(cfg).create_time_entry(&body) {
+            Ok(()) => {
+                cache::invalidate(CacheKind::TimeEntries);
+                ledger::forget_since(cfg, cfg.zone.date_of(body.start));
+                return Ok(format!("{} Tracked {} {}", HOURGLASS, fmt_time(duration), label));
+            }
+            // rejected by ClickUp itself, queueing would only replay the same error
+            Err(e) if e.status().is_some_and(|s| s.is_client_error()) => return Err(e),
+            Err(e) => Some(e),
+        }
+    };
+    match journal::enqueue(label.clone(), body) {
+        Ok(()) => Ok(format!(
+            "{} Queued {} {} offline{}, run 'sync' once you are back online",
+            HOURGLASS,
+            fmt_time(duration),
+            label,
+            failure.map(|e| format!(" ({})", e)).unwrap_or_default()
+        )),
+        Err(journal_error) => Ok(format!(
+            "{} Could not book {}{}, nor queue it: {}",
+            ERROR,
+            label,
+            failure.map(|e| format!(": {}", e)).unwrap_or_default(),
+            journal_error
+        )),
+    }
+}
+
+// tags given on the command line replace the default tags of the cfg
+fn entry_tags(cfg: &Cfg, tags: &[&str]) -> Vec<Tag> {
+    if tags.is_empty() {
+        cfg.default_tags.iter().map(|t| Tag::named(t)).collect()
+    } else {
+        tags.iter().map(|t| Tag::named(t)).collect()
+    }
+}
+
+fn fmt_entry_meta(tags: &[Tag], billable: Option<bool>) -> String {
+    let mut meta: Vec<String> = tags.iter().map(|t| format!("#{}", t.name)).collect();
+    match billable {
+        Some(true) => meta.push("billable".to_string()),
+        Some(false) => meta.push("not billable".to_string()),
+        None => {}
+    }
+    if meta.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", meta.join(", "))
+    }
+}
+
+// the ClickUp id of the task named by the checked out branch, or why there is none
+fn branch_task(cfg: &Cfg, offline: bool) -> Result<Result<String, String>, Error> {
+    let id = match git::branch_task_id(cfg) {
+        Ok(id) => id,
+        Err(e) => return Ok(Err(e)),
+    };
+    if !git::is_custom_id(&id) {
+        return Ok(Ok(id));
+    }
+    // time entries and timers take ClickUp ids only
+    if offline {
+        return Ok(Err(format!("The custom task id {} of the branch can't be resolved offline", id)));
+    }
+    Ok(Ok(Client::new(cfg).task_by_custom_id(&id)?.id))
+}
+
+pub fn time_track(args: TimeTrack, cfg: &Cfg) -> Result<String, Error> {
+    let end = Utc::now().duration_trunc(Duration::minutes(1)).unwrap();
+    let mut duration = Duration::zero();
     let mut description = "";
+    let mut offline = false;
+    let mut tags = Vec::new();
+    let mut billable = cfg.default_billable;
     for flag in args.flags {
        match flag {
-        TimeTrackFlag::Duration(dur) => duration = dur as i64 * 60 * 1000,
-        TimeTrackFlag::Description(desc) => description = desc
+        TimeTrackFlag::Duration(dur) => duration = Duration::minutes(dur as i64),
+        TimeTrackFlag::Description(desc) => description = desc,
+        TimeTrackFlag::Offline => offline = true,
+        TimeTrackFlag::Tag(tag) => tags.push(tag),
+        TimeTrackFlag::Billable(b) => billable = Some(b),
        }
     }
-    if !description.is_empty() {
-        body.insert("description".to_string(), description.to_string());
+    let branch_id;
+    let mode = match args.mode {
+        TimeTrackMode::Branch => match branch_task(cfg, offline)? {
+            Ok(id) => {
+                branch_id = id;
+                TimeTrackMode::TaskId(&branch_id)
+            }
+            Err(e) => return Ok(format!("{} {}", ERROR, e)),
+        },
+        mode => mode,
     };
-    match args.mode {
+    let (tid, label) = match mode {
+       TimeTrackMode::Branch => unreachable!(),
        TimeTrackMode::Last => {
-            time_entry = task_get_last_internal(cfg)?;
-            if let Some(_task) = time_entry.task {
-                task = _task.clone();
-                body.insert("tid".to_string(), _task.id);
-                duration = match duration {
-                    0 => end - time_entry.end.parse::<i64>().unwrap(),
-                    _ => duration as i64 * 60 * 1000 // convert minutes to milliseconds
-                };
-            } else {
-                panic!("No task id found for last time entry")
-            }
-            let start = end - duration;
-            body.insert("start".to_string(), start.to_string());
-            body.insert("end".to_string(), end.to_string());
-            body.insert("duration".to_string(), duration.to_string());
-            let url = format!("https://api.clickup.com/api/v2/team/{}/time_entries", cfg.team_id);
-            if make_post_request(cfg, url, body).is_ok() {
-               Ok(format!("{} Tracked {} for task {}", HOURGLASS, fmt_time(duration as f32 / 1000f32 / 60f32 / 60f32), task.name))
-            } else {
-                panic!("Failed to track time for task {}", task.name)
+            let Some(time_entry) = task_get_last_internal(cfg)? else {
+                return Ok(format!("{} No time entry within the last {} days to continue, pass a task id", ERROR, cfg.look_behind));
+            };
+            let Some(task) = time_entry.task.as_ref() else {
+                return Ok(format!("{} The last time entry has no task, pass a task id", ERROR));
+            };
+            if duration.is_zero() {
+                duration = end - time_entry.end_or_now();
             }
+            (Some(task.id.clone()), format!("for task {}", task.name))
        },
        TimeTrackMode::Free => {
-            if duration == 0 {
-                panic!("Duration must be set for free time tracking")
-            };
-            let start = end - duration;
-            body.insert("start".to_string(), start.to_string());
-            body.insert("end".to_string(), end.to_string());
-            body.insert("duration".to_string(), duration.to_string());
-            let url = format!("https://api.clickup.com/api/v2/team/{}/time_entries", cfg.team_id);
-            if make_post_request(cfg, url, body).is_ok() {
-               Ok(format!("{} Tracked {}", HOURGLASS, fmt_time(duration as f32 / 1000f32 / 60f32 / 60f32)))
-            } else {
-                panic!("Failed to track time")
+            if duration.is_zero() {
+                return Ok(format!("{} Tracking time without a task needs a duration, pass -d <minutes>", ERROR));
             }
+            (None, "without task".to_string())
        },
-       TimeTrackMode::TaskId(_id) => {
-           todo!()
-       }
+       TimeTrackMode::TaskId(id) => {
+            if duration.is_zero() {
+                return Ok(format!("{} Tracking time for a task id needs a duration, pass -d <minutes>", ERROR));
+            }
+            // the name is only cosmetic, don't let a failed lookup keep us from booking
+            let name = match offline {
+                true => None,
+                false => Client::new(cfg).task(id).ok().map(|t| t.name),
+            };
+            (Some(id.to_string()), format!("for task {}", name.as_deref().unwrap_or(id)))
        }
+    };
+    let tags = entry_tags(cfg, &tags);
+    let label = format!("{}{}", label, fmt_entry_meta(&tags, billable));
+    let body = NewTimeEntry {
+        start: end - duration,
+        duration,
+        tid,
+        description: Some(description.to_string()).filter(|d| !d.is_empty()),
+        tags,
+        billable,
+    };
+    book(cfg, body, label, offline)
+}
+
+pub fn tasks_list(cfg: &Cfg, list_ids: &[&str]) -> Result<String, Error> {
+    // several lists are fetched concurrently
+    let tasks = Client::new(cfg).lists_tasks(list_ids, None)?;
+    if tasks.is_empty() {
+        return Ok(format!("No open tasks in list {}", list_ids.join(", ")));
+    }
+    Ok(tasks.iter().map(fmt_task_line).collect::<Vec<String>>().join("\n"))
+}
+
+pub fn task_comments(cfg: &Cfg, task_id: &str) -> Result<String, Error> {
+    let comments = Client::new(cfg).comments(task_id)?;
+    if comments.is_empty() {
+        return Ok(format!("No comments on task {}", task_id));
+    }
+    Ok(comments.iter().map(|c| fmt_comment(c, cfg.zone)).collect::<Vec<String>>().join("\n"))
+}
+
+pub fn task_set_status(cfg: &Cfg, task: &Task, status: &str) -> Result<String, Error> {
+    Client::new(cfg).set_task_status(&task.id, status)?;
+    Ok(format!("Changed status of task {} from '{}' to '{}'", task.name, task.status.status, status))
+}
+
+// resolves a status name case-insensitively, accepting unique prefixes like "prog" for "in progress"
+fn resolve_status<'a>(statuses: &'a [Status], query: &str) -> Result<&'a Status, String> {
+    let query = query.to_lowercase();
+    if let Some(exact) = statuses.iter().find(|s| s.status.to_lowercase() == query) {
+        return Ok(exact);
+    }
+    let candidates: Vec<&Status> = statuses
+        .iter()
+        .filter(|s| s.status.to_lowercase().split_whitespace().any(|w| w.starts_with(&query)) || s.status.to_lowercase().starts_with(&query))
+        .collect();
+    let names = || statuses.iter().map(|s| s.status.as_str()).collect::<Vec<_>>().join(", ");
+    match candidates.as_slice() {
+        [single] => Ok(single),
+        [] => Err(format!("No status matching '{}', valid are: {}", query, names())),
+        _ => Err(format!("Status '{}' is ambiguous, valid are: {}", query, names())),
+    }
+}
+
+pub fn task_status(cfg: &Cfg, task_id: &str, status: &str) -> Result<String, Error> {
+    let task = Client::new(cfg).task(task_id)?;
+    let list_id = task.list.as_ref().map(|l| l.id.clone()).unwrap_or(cfg.list_id.clone());
+    let statuses = cache::list_statuses(cfg, &list_id)?;
+    match resolve_status(&statuses, status) {
+        Ok(resolved) => task_set_status(cfg, &task, &resolved.status),
+        Err(msg) => Ok(format!("{} {}", ERROR, msg)),
+    }
+}
+
+// prints candidates one per line for shell completion scripts; only reads through the cache
+pub fn complete(cfg: &Cfg, what: &str) -> Result<String, Error> {
+    let lines: Vec<String> = match what {
+        "tasks" => cache::team_tasks(cfg)?.into_iter().map(|t| format!("{}\t{}", t.id, t.name)).collect(),
+        "statuses" => cache::list_statuses(cfg, &cfg.list_id)?.into_iter().map(|s| s.status).collect(),
+        "members" => cache::members(cfg)?.into_iter().filter_map(|m| m.username).collect(),
+        "tags" => Client::new(cfg).tags()?.into_iter().map(|t| t.name).collect(),
+        "lists" => {
+            let mut lists = Vec::new();
+            for space in cache::hierarchy(cfg)?.spaces {
+                for list in space.lists {
+                    lists.push(format!("{}\t{}/{}", list.id, space.name, list.name.unwrap_or_default()));
+                }
+                for folder in space.folders {
+                    for list in folder.lists {
+                        lists.push(format!("{}\t{}/{}/{}", list.id, space.name, folder.name, list.name.unwrap_or_default()));
+                    }
+                }
+            }
+            lists
+        }
+        _ => return Ok(format!("{} Unknown completion kind '{}', valid are: tasks, statuses, members, tags, lists", ERROR, what)),
+    };
+    Ok(lines.join("\n"))
+}
+
+pub fn task_search(args: TaskSearch, cfg: &Cfg) -> Result<String, Error> {
+    let mut limit = 10;
+    let mut with_description = false;
+    for flag in args.flags {
+        match flag {
+            TaskSearchFlag::Description => with_description = true,
+            TaskSearchFlag::Limit(n) => limit = n,
+        }
+    }
+    let tasks = cache::team_tasks(cfg)?;
+    let ranked = rank(&tasks, &args.query, with_description);
+    if ranked.is_empty() {
+        return Ok(format!("No tasks matching '{}'", args.query));
+    }
+    let shown = &ranked[..ranked.len().min(limit)];
+    for (i, task) in shown.iter().enumerate() {
+        println!("{:>3}) {} [{}] ({})", i + 1, task.name, task.status.status, task.id);
+    }
+    let Some(picked) = ask_index("Pick a task (empty to quit):", shown.len()) else {
+        return Ok("No task picked".to_string());
+    };
+    let task = shown[picked];
+    match ask("[t]rack time, change [s]tatus, [o]pen or [q]uit:").as_deref() {
+        Some("t") => {
+            let Some(mins) = ask("Duration in minutes:").and_then(|m| m.parse::<u32>().ok()) else {
+                return Ok("Invalid duration, nothing tracked".to_string());
+            };
+            let description = ask("Description (optional):").unwrap_or_default();
+            let mut flags = vec![TimeTrackFlag::Duration(mins)];
+            if !description.is_empty() {
+                flags.push(TimeTrackFlag::Description(&description));
+            }
+            time_track(TimeTrack { mode: TimeTrackMode::TaskId(&task.id), flags }, cfg)
+        }
+        Some("s") => {
+            let list_id = task.list.as_ref().map(|l| l.id.clone()).unwrap_or(cfg.list_id.clone());
+            let statuses = cache::list_statuses(cfg, &list_id)?;
+            for (i, status) in statuses.iter().enumerate() {
+                println!("{:>3}) {}", i + 1, status.status);
+            }
+            match ask_index("Pick a status:", statuses.len()) {
+                Some(i) => task_set_status(cfg, task, &statuses[i].status),
+                None => Ok("Status unchanged".to_string()),
+            }
+        }
+        Some("o") => {
+            let url = task.url.clone().unwrap_or(format!("https://app.clickup.com/t/{}", task.id));
+            let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+            match Command::new(opener).arg(&url).spawn() {
+                Ok(_) => Ok(format!("Opened {}", url)),
+                Err(_) => Ok(format!("Could not run {}, open {} manually", opener, url)),
+            }
+        }
+        _ => Ok("Nothing done".to_string()),
     }
+}
 
-#[allow(dead_code)]
-pub fn tasks_list() -> Result<(), reqwest::Error> {
-    todo!()
+pub fn task_create_comment(cfg: &Cfg, task_id: &str, text: &str) -> Result<String, Error> {
+    Client::new(cfg).create_comment(task_id, text)?;
+    Ok(format!("Commented on task {}", task_id))
 }
 
-#[allow(dead_code)]
-pub fn task_set_status() -> Result<(), reqwest::Error> {
-    todo!()
+pub fn cache_clear(kinds: &[CacheKind]) -> String {
+    match cache::clear(kinds) {
+        Some(dir) if kinds.is_empty() => format!("Cleared cache at {}", dir.display()),
+        Some(dir) => {
+            let names: Vec<&str> = kinds.iter().map(|kind| kind.name()).collect();
+            format!("Cleared cached {} at {}", names.join(", "), dir.display())
+        }
+        None => format!("{} Could not determine the cache directory; expose $HOME or $XDG_CACHE_HOME first!", ERROR),
+    }
+}
+
+pub fn fmt_sync_report(report: &SyncReport) -> String {
+    let mut out = format!(
+        "{} Synced {} entries, skipped {} duplicates, {} still queued",
+        HOURGLASS, report.posted, report.duplicates, report.remaining
+    );
+    for (label, reason) in &report.conflicts {
+        out.push_str(&format!("\n{} [CONFLICT] {}: {}", ERROR, label, reason));
+    }
+    if let Some(e) = &report.interrupted {
+        out.push_str(&format!("\n{} Sync interrupted: {}", ERROR, e));
+    }
+    if let Some(e) = &report.journal_error {
+        out.push_str(&format!("\n{} Could not rewrite the journal: {}", ERROR, e));
+    }
+    out
+}
+
+fn fmt_queued(entry: &QueuedEntry) -> String {
+    let duration = hours(entry.body.duration);
+    match &entry.conflict {
+        Some(reason) => format!("{} {} ({}) [CONFLICT: {}]", HOURGLASS, entry.label, fmt_time(duration), reason),
+        None => format!("{} {} ({})", HOURGLASS, entry.label, fmt_time(duration)),
+    }
 }
 
-#[allow(dead_code)]
-pub fn task_create_comment() -> Result<(), reqwest::Error> {
-    todo!()
+pub fn sync(cfg: &Cfg, options: &SyncOptions) -> Result<String, Error> {
+    if journal::is_empty() {
+        return Ok("Nothing to sync".to_string());
+    }
+    Ok(fmt_sync_report(&journal::sync(cfg, options)?))
+}
+
+pub fn sync_list() -> String {
+    let queued = journal::load();
+    if queued.is_empty() {
+        return "Offline journal is empty".to_string();
+    }
+    queued.iter().map(fmt_queued).collect::<Vec<String>>().join("\n")
+}
+
+fn timer_label(entry: &TimeEntry) -> String {
+    entry.task.as_ref().map_or("without task".to_string(), |task| format!("for task {}", task.name))
+}
+
+pub fn timer_start(cfg: &Cfg, task_id: Option<&str>, flags: Vec<TimeTrackFlag>) -> Result<String, Error> {
+    let branch_id;
+    let task_id = match task_id {
+        Some("branch") => match branch_task(cfg, false)? {
+            Ok(id) => {
+                branch_id = id;
+                Some(branch_id.as_str())
+            }
+            Err(e) => return Ok(format!("{} {}", ERROR, e)),
+        },
+        task_id => task_id,
+    };
+    let mut body = StartTimer {
+        tid: task_id.map(str::to_string),
+        billable: cfg.default_billable,
+        ..Default::default()
+    };
+    let mut tags = Vec::new();
+    for flag in flags {
+        match flag {
+            TimeTrackFlag::Description(desc) => body.description = Some(desc.to_string()),
+            TimeTrackFlag::Tag(tag) => tags.push(tag),
+            TimeTrackFlag::Billable(b) => body.billable = Some(b),
+            // timers have no preset duration and can't run offline
+            TimeTrackFlag::Duration(_) | TimeTrackFlag::Offline => {}
+        }
+    }
+    body.tags = entry_tags(cfg, &tags);
+    let client = Client::new(cfg);
+    let previous = client.current_timer()?;
+    let started = client.start_timer(&body)?;
+    cache::invalidate(CacheKind::TimeEntries);
+    cache::invalidate(CacheKind::Timer);
+    let mut out = String::new();
+    if let Some(previous) = previous {
+        out.push_str(&format!("{} Stopped timer {} after {}\n", HOURGLASS, timer_label(&previous), fmt_time(hours(previous.elapsed()))));
+    }
+    match started {
+        Some(entry) => out.push_str(&format!("{} Started timer {}{}", HOURGLASS, timer_label(&entry), fmt_entry_meta(&body.tags, body.billable))),
+        None => out.push_str(&format!("{} Started timer{}", HOURGLASS, fmt_entry_meta(&body.tags, body.billable))),
+    }
+    Ok(out)
+}
+
+pub fn timer_stop(cfg: &Cfg) -> Result<String, Error> {
+    match Client::new(cfg).stop_timer()? {
+        Some(entry) => {
+            cache::invalidate(CacheKind::TimeEntries);
+            cache::invalidate(CacheKind::Timer);
+            ledger::forget_since(cfg, cfg.zone.date_of(entry.start));
+            Ok(format!("{} Stopped timer {} after {}", HOURGLASS, timer_label(&entry), fmt_time(hours(entry.duration))))
+        }
+        None => Ok("No timer running".to_string()),
+    }
+}
+
+pub fn timer_current(cfg: &Cfg) -> Result<String, Error> {
+    match Client::new(cfg).current_timer()? {
+        Some(entry) => {
+            let since = cfg.zone.naive(entry.start);
+            Ok(format!(
+                "{} Timer running {} since {} ({})",
+                HOURGLASS,
+                timer_label(&entry),
+                since.format("%H:%M"),
+                fmt_time(hours(entry.elapsed()))
+            ))
+        }
+        None => Ok("No timer running".to_string()),
+    }
+}
+
+pub fn tag(cfg: &Cfg, cmd: TagCmd) -> Result<String, Error> {
+    let client = Client::new(cfg);
+    match cmd {
+        TagCmd::List => {
+            let tags = client.tags()?;
+            if tags.is_empty() {
+                return Ok("No time entry tags in this team".to_string());
+            }
+            Ok(tags.iter().map(|t| format!("{} {}", TAG, t.name)).collect::<Vec<String>>().join("\n"))
+        }
+        TagCmd::Create { name, fg, bg, entry_ids } => {
+            let entry_ids: Vec<String> = entry_ids.iter().map(|id| id.to_string()).collect();
+            let tag = Tag {
+                name: name.to_string(),
+                tag_fg: fg.map(str::to_string),
+                tag_bg: bg.map(str::to_string),
+            };
+            client.add_tags(&entry_ids, &[tag])?;
+            cache::invalidate(CacheKind::TimeEntries);
+            Ok(format!("{} Tagged {} time entries with '{}'", TAG, entry_ids.len(), name))
+        }
+        TagCmd::Rename { name, new_name } => {
+            let rename = TagRename {
+                name: name.to_string(),
+                new_name: new_name.to_string(),
+                tag_fg: None,
+                tag_bg: None,
+            };
+            client.rename_tag(&rename)?;
+            cache::invalidate(CacheKind::TimeEntries);
+            Ok(format!("{} Renamed tag '{}' to '{}'", TAG, name, new_name))
+        }
+        TagCmd::Delete { name, from } => {
+            let (entry_ids, scope): (Vec<String>, String) = match from {
+                TagScope::Entries(ids) => (ids.iter().map(|id| id.to_string()).collect(), String::new()),
+                TagScope::Since(day) => {
+                    let range = Range::days(cfg.zone, day, cfg.zone.today());
+                    let tagged = client
+                        .time_entries(range.start, range.end)?
+                        .into_iter()
+                        .filter(|entry| entry.tags.iter().any(|t| t.name == name))
+                        .map(|entry| entry.id)
+                        .collect();
+                    (tagged, format!(" since {}", day))
+                }
+            };
+            if entry_ids.is_empty() {
+                return Ok(format!("No time entry{} is tagged '{}'", scope, name));
+            }
+            client.remove_tags(&entry_ids, &[Tag::named(name)])?;
+            cache::invalidate(CacheKind::TimeEntries);
+            Ok(format!("{} Removed tag '{}' from {} time entries{}", TAG, name, entry_ids.len(), scope))
+        }
+    }
+}
+
+pub fn vacation(cfg: &Cfg, cmd: VacationCmd) -> String {
+    match cmd {
+        VacationCmd::Add(from, to) => {
+            if to < from {
+                return format!("{} Vacation ends before it starts", ERROR);
+            }
+            // the quota of the days before they are booked as vacation, i.e. the hours we take off
+            let hours = cfg.quota_between(from, to);
+            match calendar::add_vacation(from, to) {
+                Ok(()) => format!("{} Added vacation from {} to {}, {} off", PALM_TREE, from, to, fmt_time(hours)),
+                Err(e) => format!("{} Could not store the vacation: {}", ERROR, e),
+            }
+        }
+        VacationCmd::List => {
+            let vacations = calendar::vacations();
+            if vacations.is_empty() {
+                return "No vacations stored".to_string();
+            }
+            vacations
+                .iter()
+                .map(|v| format!("{} {} to {}", PALM_TREE, v.from, v.to))
+                .collect::<Vec<String>>()
+                .join("\n")
+        }
+        VacationCmd::Remove(from) => match calendar::remove_vacation(from) {
+            Ok(0) => format!("No vacation starts on {}", from),
+            Ok(_) => format!("{} Removed vacation starting on {}", PALM_TREE, from),
+            Err(e) => format!("{} Could not update the vacations: {}", ERROR, e),
+        },
+    }
+}
+
+pub fn balance(cfg: &Cfg, args: Balance) -> Result<String, Error> {
+    let Some(since) = args.since.or(cfg.balance_start) else {
+        return Ok(format!("{} No start date, set balance_start=YYYY-MM-DD in the cfg or pass --since", ERROR));
+    };
+    if since > cfg.zone.today() {
+        return Ok(format!("{} The balance starts in the future ({})", ERROR, since));
+    }
+    let days = ledger::days(cfg, since)?;
+    let fmt_period = |label: String, p: &Period| {
+        format!("{: <12} {: >8} / {: >8} {: >9} {: >10}", label, fmt_time(p.tracked), fmt_time(p.expected), fmt_balance(p.delta()), fmt_balance(p.balance))
+    };
+    let mut lines = vec![format!("{: <12} {: >8}   {: >8} {: >9} {: >10}", "", "tracked", "expected", "delta", "balance")];
+    match args.view {
+        BalanceView::Days => {
+            let mut balance = 0.0;
+            for day in &days {
+                balance += day.delta();
+                let period = Period { first: day.date, tracked: day.tracked, expected: day.expected, balance };
+                lines.push(fmt_period(day.date.format("%a %d/%m").to_string(), &period));
+            }
+        }
+        BalanceView::Weeks => {
+            for period in ledger::group(&days, ledger::week_of) {
+                lines.push(fmt_period(format!("W{:02} {}", period.first.iso_week().week(), period.first.format("%d/%m")), &period));
+            }
+        }
+        BalanceView::Months => {
+            for period in ledger::group(&days, ledger::month_of) {
+                lines.push(fmt_period(period.first.format("%b %Y").to_string(), &period));
+            }
+        }
+    }
+    let total: f32 = days.iter().map(|d| d.delta()).sum();
+    // today is usually still in progress, so also show where we stood last night
+    let today = days.last().map(|d| d.delta()).unwrap_or(0.0);
+    lines.push(format!(
+        "{} Balance since {}: {} ({} until yesterday)",
+        HOURGLASS,
+        since.format("%d/%m/%Y"),
+        fmt_balance(total),
+        fmt_balance(total - today)
+    ));
+    Ok(lines.join("\n"))
+}
+
+fn fmt_entry(cfg: &Cfg, entry: &TimeEntry) -> String {
+    let task = entry.task.as_ref().map_or("no task".to_string(), |t| t.name.clone());
+    format!(
+        "{}-{} {} ({})",
+        cfg.zone.naive(entry.start).format("%d/%m %H:%M"),
+        cfg.zone.naive(entry.end_or_now()).format("%H:%M"),
+        task,
+        fmt_time(hours(entry.elapsed()))
+    )
+}
+
+fn fmt_issue(cfg: &Cfg, issue: &Issue) -> String {
+    match issue {
+        Issue::Overlap { earlier, later } => format!(
+            "Overlap of {}: {} and {}",
+            fmt_time(hours(earlier.end_or_now().min(later.end_or_now()) - later.start)),
+            fmt_entry(cfg, earlier),
+            fmt_entry(cfg, later)
+        ),
+        Issue::Gap { start, end, .. } => format!(
+            "Gap of {} from {} to {}",
+            fmt_time(hours(*end - *start)),
+            cfg.zone.naive(*start).format("%d/%m %H:%M"),
+            cfg.zone.naive(*end).format("%H:%M")
+        ),
+        Issue::NoTask(entry) => format!("No task: {}", fmt_entry(cfg, entry)),
+        Issue::Long(entry) => format!("Longer than {}h: {}", cfg.long_entry_hours, fmt_entry(cfg, entry)),
+    }
+}
+
+// moves the end of an entry, keeping its start
+fn trim_entry(cfg: &Cfg, entry: &TimeEntry, end: DateTime<Utc>) -> Result<String, Error> {
+    let update = TimeEntryUpdate {
+        start: entry.start,
+        end,
+        duration: end - entry.start,
+    };
+    Client::new(cfg).update_time_entry(&entry.id, &update)?;
+    Ok(format!("Trimmed to {}", fmt_time(hours(update.duration))))
+}
+
+fn delete_entry(cfg: &Cfg, entry: &TimeEntry) -> Result<String, Error> {
+    Client::new(cfg).delete_time_entry(&entry.id)?;
+    Ok(format!("Deleted {}", fmt_entry(cfg, entry)))
+}
+
+// asks how to fix an issue and applies the answer; None if it was left as it is
+fn fix_issue(cfg: &Cfg, issue: &Issue) -> Result<Option<String>, Error> {
+    let done = match issue {
+        Issue::Overlap { earlier, later } => match ask("  [t]rim the earlier entry, [d]elete the later one or [s]kip:").as_deref() {
+            Some("t") => trim_entry(cfg, earlier, later.start)?,
+            Some("d") => delete_entry(cfg, later)?,
+            _ => return Ok(None),
+        },
+        Issue::Gap { start, end, before } => {
+            let Some(task) = before.as_ref().and_then(|b| b.task.as_ref()) else {
+                return Ok(None);
+            };
+            match ask(&format!("  [f]ill with {} or [s]kip:", task.name)).as_deref() {
+                Some("f") => {
+                    let tags = entry_tags(cfg, &[]);
+                    let label = format!("for task {}{}", task.name, fmt_entry_meta(&tags, cfg.default_billable));
+                    let body = NewTimeEntry {
+                        start: *start,
+                        duration: *end - *start,
+                        tid: Some(task.id.clone()),
+                        description: None,
+                        tags,
+                        billable: cfg.default_billable,
+                    };
+                    book(cfg, body, label, false)?
+                }
+                _ => return Ok(None),
+            }
+        }
+        Issue::NoTask(entry) => match ask("  [d]elete or [s]kip:").as_deref() {
+            Some("d") => delete_entry(cfg, entry)?,
+            _ => return Ok(None),
+        },
+        Issue::Long(entry) => match ask("  [t]rim to a duration, [d]elete or [s]kip:").as_deref() {
+            Some("t") => {
+                let Some(mins) = ask("  Duration in minutes:").and_then(|m| m.parse::<i64>().ok()).filter(|m| *m > 0) else {
+                    return Ok(None);
+                };
+                trim_entry(cfg, entry, entry.start + Duration::minutes(mins))?
+            }
+            Some("d") => delete_entry(cfg, entry)?,
+            _ => return Ok(None),
+        },
+    };
+    Ok(Some(done))
+}
+
+pub fn time_check(cfg: &Cfg, first: NaiveDate, last: NaiveDate, report_only: bool) -> Result<String, Error> {
+    let range = Range::days(cfg.zone, first, last);
+    let entries = Client::new(cfg).time_entries_overlapping(&range)?;
+    let issues = check(cfg, &entries, first, last);
+    let days = if first == last { first.format("%d/%m/%Y").to_string() } else { format!("{} to {}", first.format("%d/%m/%Y"), last.format("%d/%m/%Y")) };
+    if issues.is_empty() {
+        return Ok(format!("{} No issues in the time entries of {}", HOURGLASS, days));
+    }
+    let mut fixed = 0;
+    for (i, issue) in issues.iter().enumerate() {
+        println!("{:>3}) {}", i + 1, fmt_issue(cfg, issue));
+        if report_only {
+            continue;
+        }
+        if let Some(done) = fix_issue(cfg, issue)? {
+            println!("  {}", done);
+            fixed += 1;
+        }
+    }
+    if fixed > 0 {
+        cache::invalidate(CacheKind::TimeEntries);
+        ledger::forget_since(cfg, first);
+    }
+    Ok(format!("{} {} issues in the time entries of {}, {} fixed", ERROR, issues.len(), days, fixed))
+}
+
+// distinct tasks of the entries, most recently tracked first
+pub fn recent_tasks(entries: &[TimeEntry], limit: usize) -> Vec<Task> {
+    let mut sorted: Vec<&TimeEntry> = entries.iter().collect();
+    sorted.sort_by_key(|entry| std::cmp::Reverse(entry.start));
+    let mut tasks: Vec<Task> = Vec::new();
+    for task in sorted.into_iter().filter_map(|entry| entry.task.as_ref()) {
+        if tasks.len() < limit && !tasks.iter().any(|t| t.id == task.id) {
+            tasks.push(task.clone());
+        }
+    }
+    tasks
+}
+
+pub fn time_fill(cfg: &Cfg, first: NaiveDate, last: NaiveDate) -> Result<String, Error> {
+    let client = Client::new(cfg);
+    // look further back than the filled days so there are recent tasks to pick from
+    let range = Range::days(cfg.zone, first - Days::new(cfg.look_behind), last);
+    let entries = client.time_entries_overlapping(&range)?;
+    let gaps = gaps_between(cfg, &entries, first, last);
+    if gaps.is_empty() {
+        return Ok(format!("{} No gaps within the working hours to fill", HOURGLASS));
+    }
+    let recent = recent_tasks(&entries, 9);
+    for (i, task) in recent.iter().enumerate() {
+        println!("{:>3}) {} ({})", i + 1, task.name, task.id);
+    }
+    let tags = entry_tags(cfg, &[]);
+    let mut bodies = Vec::new();
+    let mut labels = Vec::new();
+    for gap in &gaps {
+        let Issue::Gap { start, end, before } = gap else { continue };
+        let last_task = before.and_then(|b| b.task.as_ref());
+        println!("{}", fmt_issue(cfg, gap));
+        let question = match last_task {
+            Some(task) => format!("  Task: [enter] {}, a number above, a task id or [s]kip:", task.name),
+            None => "  Task: a number above, a task id or [s]kip:".to_string(),
+        };
+        let Some(answer) = ask(&question) else { break };
+        let task = match answer.as_str() {
+            "" => last_task.map(|t| (t.id.clone(), t.name.clone())),
+            "s" => None,
+            _ => match answer.parse::<usize>() {
+                Ok(n) if n >= 1 && n <= recent.len() => Some((recent[n - 1].id.clone(), recent[n - 1].name.clone())),
+                // the name is only cosmetic, an unknown id is still booked and rejected by ClickUp
+                _ => Some((answer.clone(), client.task(&answer).map(|t| t.name).unwrap_or(answer.clone()))),
+            },
+        };
+        let Some((tid, name)) = task else { continue };
+        labels.push(format!("{} for task {}", fmt_time(hours(*end - *start)), name));
+        bodies.push(NewTimeEntry {
+            start: *start,
+            duration: *end - *start,
+            tid: Some(tid),
+            description: None,
+            tags: tags.clone(),
+            billable: cfg.default_billable,
+        });
+    }
+    if bodies.is_empty() {
+        return Ok("Nothing to book".to_string());
+    }
+    let total: f32 = bodies.iter().map(|b| hours(b.duration)).sum();
+    if !matches!(ask(&format!("Book {} entries, {} in total? [y/n]", bodies.len(), fmt_time(total))).as_deref(), Some("y")) {
+        return Ok("Nothing booked".to_string());
+    }
+    Ok(book_all(cfg, &bodies, &labels, first))
+}
+
+// posts the entries concurrently, queueing those that didn't go through; `first` is the earliest day booked
+fn book_all(cfg: &Cfg, bodies: &[NewTimeEntry], labels: &[String], first: NaiveDate) -> String {
+    let mut out = Vec::new();
+    let mut posted = 0;
+    for ((body, label), res) in bodies.iter().zip(labels).zip(Client::new(cfg).create_time_entries(bodies)) {
+        match res {
+            Ok(()) => posted += 1,
+            // rejected by ClickUp itself, queueing would only replay the same error
+            Err(e) if e.status().is_some_and(|s| s.is_client_error()) => out.push(format!("{} Could not book {}: {}", ERROR, label, e)),
+            Err(e) => match journal::enqueue(label.clone(), body.clone()) {
+                Ok(()) => out.push(format!("{} Queued {} offline ({})", HOURGLASS, label, e)),
+                Err(journal_error) => out.push(format!("{} Could not book {}: {}, nor queue it: {}", ERROR, label, e, journal_error)),
+            },
+        }
+    }
+    if posted > 0 {
+        cache::invalidate(CacheKind::TimeEntries);
+        ledger::forget_since(cfg, first);
+    }
+    out.insert(0, format!("{} Booked {} of {} entries", HOURGLASS, posted, bodies.len()));
+    out.join("\n")
+}
+
+/// Books every gap within the working hours of `day` on one task, without asking.
+pub fn book_gaps(cfg: &Cfg, day: NaiveDate, task: &Task) -> Result<String, Error> {
+    let entries = Client::new(cfg).time_entries_overlapping(&Range::day(cfg.zone, day))?;
+    let tags = entry_tags(cfg, &[]);
+    let mut bodies = Vec::new();
+    let mut labels = Vec::new();
+    for gap in gaps_between(cfg, &entries, day, day) {
+        let Issue::Gap { start, end, .. } = gap else { continue };
+        labels.push(format!("{} for task {}", fmt_time(hours(end - start)), task.name));
+        bodies.push(NewTimeEntry {
+            start,
+            duration: end - start,
+            tid: Some(task.id.clone()),
+            description: None,
+            tags: tags.clone(),
+            billable: cfg.default_billable,
+        });
+    }
+    if bodies.is_empty() {
+        return Ok(format!("{} No gaps within the working hours to fill", HOURGLASS));
+    }
+    Ok(book_all(cfg, &bodies, &labels, day))
+}
+
+// tracked time closer than this to the quota counts as done rather than under or over
+const QUOTA_TOLERANCE_HOURS: f32 = 5.0 / 60.0;
+
+fn quota_class(tracked: f32, quota: f32) -> &'static str {
+    if tracked < quota - QUOTA_TOLERANCE_HOURS {
+        "under"
+    } else if tracked > quota + QUOTA_TOLERANCE_HOURS {
+        "over"
+    } else {
+        "done"
+    }
+}
+
+fn fmt_clock(duration: Duration) -> String {
+    format!("{}:{:02}", duration.num_hours(), duration.num_minutes() % 60)
+}
+
+pub fn statusbar(cfg: &Cfg, format: StatusbarFormat) -> String {
+    let today = cfg.zone.today();
+    // served from the cache so status bars polling every few seconds stay within the rate limit
+    let state = cache::day_entries(cfg, today).and_then(|entries| Ok((entries, cache::current_timer(cfg)?)));
+    let (mut entries, timer) = match state {
+        Ok(state) => state,
+        Err(e) => {
+            return match format {
+                StatusbarFormat::Waybar => json!({ "text": format!("{} cupcli", ERROR), "tooltip": e.to_string(), "class": "error" }).to_string(),
+                StatusbarFormat::I3blocks => format!("{} cupcli\n{}\n#ff5555", ERROR, ERROR),
+                StatusbarFormat::Polybar => format!("{} cupcli", ERROR),
+            };
+        }
+    };
+    // a timer started after the entries were cached still counts
+    if let Some(timer) = &timer {
+        if !entries.iter().any(|e| e.id == timer.id) {
+            entries.push(timer.clone());
+        }
+    }
+    let tracked = calculate_time_in(&entries, &Range::day(cfg.zone, today));
+    let quota = cfg.quota(today);
+    let class = quota_class(tracked, quota);
+    let mut text = format!("{} {}/{}", HOURGLASS, fmt_time(tracked), fmt_time(quota));
+    let mut tooltip = vec![format!("Tracked today: {} out of {}{}", fmt_time(tracked), fmt_time(quota), fmt_day_off(cfg, today))];
+    if let Some(timer) = &timer {
+        let task = timer.task.as_ref().map_or("no task", |t| t.name.as_str());
+        text.push_str(&format!(" {} {}", STOPWATCH, fmt_clock(timer.elapsed())));
+        tooltip.push(format!("Timer running {} since {}", timer_label(timer), cfg.zone.naive(timer.start).format("%H:%M")));
+        if format != StatusbarFormat::Waybar {
+            text.push_str(&format!(" {}", task));
+        }
+    }
+    let queued = journal::load().len();
+    if queued > 0 {
+        tooltip.push(format!("{} entries waiting in the offline journal", queued));
+    }
+    match format {
+        StatusbarFormat::Waybar => {
+            let mut classes = vec![class];
+            if timer.is_some() {
+                classes.push("running");
+            }
+            let percentage = if quota > 0.0 { (tracked / quota * 100.0).clamp(0.0, 100.0).round() as u32 } else { 100 };
+            json!({ "text": text, "tooltip": tooltip.join("\n"), "class": classes, "percentage": percentage }).to_string()
+        }
+        StatusbarFormat::I3blocks => {
+            let color = match class {
+                "over" => "#ffb86c",
+                "done" => "#50fa7b",
+                _ => "",
+            };
+            format!("{}\n{} {}\n{}", text, HOURGLASS, fmt_time(tracked), color).trim_end().to_string()
+        }
+        StatusbarFormat::Polybar => text,
+    }
+}
+
+// meant for cron: prints nothing unless it reminded or failed to
+pub fn remind(cfg: &Cfg) -> Result<String, Error> {
+    let today = cfg.zone.today();
+    // a timer started a minute ago on another device must not trigger a reminder
+    let fresh = Cfg {
+        cache_mode: CacheMode::Refresh,
+        ..cfg.clone()
+    };
+    let timer = cache::current_timer(&fresh)?;
+    let entries = cache::day_entries(cfg, today)?;
+    match remind::remind(cfg, timer.as_ref(), &entries) {
+        Ok(Some(since)) => Ok(format!("{} Nothing tracked since {}, reminded", HOURGLASS, cfg.zone.naive(since).format("%H:%M"))),
+        Ok(None) => Ok(String::new()),
+        Err(e) => Ok(format!("{} Could not send the reminder: {}", ERROR, e)),
+    }
+}
+
+fn fmt_countdown(left: Duration) -> String {
+    format!("{:02}:{:02}", left.num_minutes(), left.num_seconds() % 60)
+}
+
+// the hook or desktop notification of a transition must not end the run
+fn pomodoro_notify(cfg: &Cfg, summary: &str, body: &str) {
+    if let Err(e) = remind::notify(cfg.pomodoro_hook.as_deref(), summary, body) {
+        eprintln!("\n[WARNING] Could not notify: {}", e);
+    }
+}
+
+// counts down to the end of the phase on one terminal line
+fn countdown(current: &Pomodoro) {
+    let mut stdout = io::stdout();
+    loop {
+        let left = current.phase_end - Utc::now();
+        if left <= Duration::zero() {
+            break;
+        }
+        print!("\r{} {} {}/{}  {} left ", TOMATO, current.phase.name(), current.round, current.plan.rounds, fmt_countdown(left));
+        let _ = stdout.flush();
+        thread::sleep(left.to_std().unwrap_or_default().min(std::time::Duration::from_secs(1)));
+    }
+    println!();
+}
+
+// stops the timer of the work block, unless it was stopped or replaced elsewhere in the meantime
+fn pomodoro_stop_timer(cfg: &Cfg, current: &Pomodoro) -> Result<Option<TimeEntry>, Error> {
+    let client = Client::new(cfg);
+    let running = client.current_timer()?.filter(|timer| current.entry_id.as_ref() == Some(&timer.id));
+    if running.is_none() {
+        return Ok(None);
+    }
+    let stopped = client.stop_timer()?;
+    cache::invalidate(CacheKind::TimeEntries);
+    cache::invalidate(CacheKind::Timer);
+    if let Some(entry) = &stopped {
+        ledger::forget_since(cfg, cfg.zone.date_of(entry.start));
+    }
+    Ok(stopped)
+}
+
+fn run_pomodoro(cfg: &Cfg, mut current: Pomodoro) -> Result<String, Error> {
+    let client = Client::new(cfg);
+    let mut tags = entry_tags(cfg, &[]);
+    tags.push(Tag::named(pomodoro::TAG));
+    println!("{} Pomodoro on task {}, interrupt with Ctrl-C and continue with `cupcli timer pomodoro resume`", TOMATO, current.task_name);
+    loop {
+        if current.phase == Phase::Work && current.entry_id.is_none() {
+            let body = StartTimer {
+                tid: Some(current.task_id.clone()),
+                tags: tags.clone(),
+                billable: cfg.default_billable,
+                ..Default::default()
+            };
+            current.entry_id = client.start_timer(&body)?.map(|entry| entry.id);
+            cache::invalidate(CacheKind::TimeEntries);
+            cache::invalidate(CacheKind::Timer);
+        }
+        if let Err(e) = pomodoro::save(&current) {
+            eprintln!("[WARNING] Could not store the pomodoro, it can't be resumed: {}", e);
+        }
+        let until = cfg.zone.naive(current.phase_end).format("%H:%M");
+        pomodoro_notify(cfg, &format!("{} {}/{}", current.phase.name(), current.round, current.plan.rounds), &format!("{} until {}", current.task_name, until));
+        countdown(&current);
+        if current.phase == Phase::Work {
+            pomodoro_stop_timer(cfg, &current)?;
+        }
+        match current.next(Utc::now()) {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    pomodoro::clear();
+    let done = format!("{} work blocks of {} minutes on task {}", current.plan.rounds, current.plan.work_mins, current.task_name);
+    pomodoro_notify(cfg, "Pomodoro done", &done);
+    Ok(format!("{} Done: {}", TOMATO, done))
+}
+
+pub fn pomodoro(cfg: &Cfg, cmd: PomodoroCmd) -> Result<String, Error> {
+    let client = Client::new(cfg);
+    match cmd {
+        PomodoroCmd::Start { task, plan } => {
+            if let Some(left) = pomodoro::load() {
+                return Ok(format!(
+                    "{} A pomodoro on task {} is still in progress, continue it with `cupcli timer pomodoro resume` or drop it with `cupcli timer pomodoro stop`",
+                    ERROR, left.task_name
+                ));
+            }
+            let (task_id, task_name) = if task == "last" {
+                match task_get_last_internal(cfg)?.and_then(|entry| entry.task) {
+                    Some(task) => (task.id, task.name),
+                    None => return Ok(format!("{} No task tracked within the last {} days", ERROR, cfg.look_behind)),
+                }
+            } else {
+                (task.to_string(), client.task(task)?.name)
+            };
+            // a timer that is already running would otherwise be stopped without a word
+            if let Some(previous) = client.current_timer()? {
+                println!("{} Stopping timer {} after {}", HOURGLASS, timer_label(&previous), fmt_time(hours(previous.elapsed())));
+            }
+            run_pomodoro(cfg, Pomodoro::new(task_id, task_name, plan, Utc::now()))
+        }
+        PomodoroCmd::Resume => {
+            let Some(mut current) = pomodoro::load() else {
+                return Ok("No pomodoro to resume".to_string());
+            };
+            let now = Utc::now();
+            if current.phase_end <= now {
+                // the phase ended while nobody was watching, a work block is cut back to its end
+                if current.phase == Phase::Work {
+                    if let Some(entry) = pomodoro_stop_timer(cfg, &current)? {
+                        let end = current.phase_end.max(entry.start);
+                        client.update_time_entry(&entry.id, &TimeEntryUpdate { start: entry.start, end, duration: end - entry.start })?;
+                    }
+                }
+                match current.next(now) {
+                    Some(next) => current = next,
+                    None => {
+                        pomodoro::clear();
+                        return Ok(format!("{} The pomodoro on task {} had already ended", TOMATO, current.task_name));
+                    }
+                }
+            } else if current.phase == Phase::Work && client.current_timer()?.is_none_or(|timer| current.entry_id.as_ref() != Some(&timer.id)) {
+                // the timer was stopped elsewhere, a new one runs for the rest of the block
+                current.entry_id = None;
+            }
+            run_pomodoro(cfg, current)
+        }
+        PomodoroCmd::Stop => {
+            let Some(current) = pomodoro::load() else {
+                return Ok("No pomodoro running".to_string());
+            };
+            let stopped = if current.phase == Phase::Work { pomodoro_stop_timer(cfg, &current)? } else { None };
+            pomodoro::clear();
+            Ok(match stopped {
+                Some(entry) => format!("{} Stopped the pomodoro and its timer after {}", TOMATO, fmt_time(hours(entry.duration))),
+                None => format!("{} Stopped the pomodoro", TOMATO),
+            })
+        }
+    }
+}
+
+// marks the hooks written by `git install-hooks`, other hooks are left alone
+const HOOK_MARKER: &str = "# installed by cupcli git install-hooks";
+
+pub fn git_install_hooks(force: bool) -> String {
+    let Some(dir) = git::hooks_dir() else {
+        return format!("{} Not inside a git repository", ERROR);
+    };
+    let exe = env::current_exe().map_or("cupcli".to_string(), |exe| exe.display().to_string());
+    let mut out = Vec::new();
+    for hook in ["prepare-commit-msg", "post-commit"] {
+        let path = dir.join(hook);
+        if fs::read_to_string(&path).is_ok_and(|existing| !existing.contains(HOOK_MARKER)) && !force {
+            out.push(format!("{} Kept the existing {} hook, replace it with --force", ERROR, hook));
+            continue;
+        }
+        // a failing prepare-commit-msg hook aborts the commit, cupcli must never keep anyone from committing
+        let script = format!("#!/bin/sh\n{}\n\"{}\" git {} \"$@\" || true\n", HOOK_MARKER, exe, hook);
+        let written = fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&path, script))
+            .and_then(|_| fs::set_permissions(&path, fs::Permissions::from_mode(0o755)));
+        match written {
+            Ok(()) => out.push(format!("Installed the {} hook at {}", hook, path.display())),
+            Err(e) => out.push(format!("{} Could not install the {} hook: {}", ERROR, hook, e)),
+        }
+    }
+    if git::hooks_path().is_some() {
+        out.push(format!("core.hooksPath is set, git runs the hooks in {} instead of .git/hooks", dir.display()));
+    }
+    if project::find().is_none() {
+        out.push("No .cupcli.toml found, the hooks do nothing until the project opts in under [git]".to_string());
+    }
+    out.join("\n")
+}
+
+// the hook settings of the project, None outside of a project
+fn git_hooks() -> Option<GitHooks> {
+    match project::load() {
+        Ok(project) => project.map(|(_, project)| project.git),
+        Err(e) => {
+            eprintln!("[WARNING] Ignoring {}", e);
+            None
+        }
+    }
+}
+
+// prints nothing, whatever it prints would show up in the middle of `git commit`
+pub fn git_prepare_commit_msg(cfg: &Cfg, file: &str, source: Option<&str>) -> String {
+    // merges and squashes bring their own messages, amended and reused messages are linked already
+    if !git_hooks().is_some_and(|hooks| hooks.link_commits) || matches!(source, Some("merge" | "squash" | "commit")) {
+        return String::new();
+    }
+    let (Ok(id), Ok(message)) = (git::branch_task_id(cfg), fs::read_to_string(file)) else {
+        return String::new();
+    };
+    if let Some(linked) = git::link_message(&message, &git::task_ref(&id)) {
+        if let Err(e) = fs::write(file, linked) {
+            eprintln!("[WARNING] Could not link the commit message: {}", e);
+        }
+    }
+    String::new()
+}
+
+struct Commit {
+    hash: String,
+    time: DateTime<Utc>,
+    subject: String,
+}
+
+// HEAD and its first parent, if it has one; git runs the hook, so there is a git binary
+fn last_commits() -> Vec<Commit> {
+    let Ok(output) = Command::new("git").args(["log", "-2", "--first-parent", "--format=%H%x1f%ct%x1f%s"]).output() else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\x1f');
+            let hash = fields.next()?.to_string();
+            let time = DateTime::from_timestamp(fields.next()?.parse::<i64>().ok()?, 0)?;
+            Some(Commit { hash, time, subject: fields.next().unwrap_or_default().to_string() })
+        })
+        .collect()
+}
+
+pub fn git_post_commit(cfg: &Cfg) -> Result<String, Error> {
+    let Some(hooks) = git_hooks() else {
+        return Ok(String::new());
+    };
+    if !(hooks.comment_commits || hooks.book_commits) || git::rebasing() {
+        return Ok(String::new());
+    }
+    let task_id = match branch_task(cfg, false)? {
+        Ok(id) => id,
+        Err(_) => return Ok(String::new()),
+    };
+    let commits = last_commits();
+    let Some(head) = commits.first() else {
+        return Ok(String::new());
+    };
+    let mut out = Vec::new();
+    if hooks.comment_commits {
+        let branch = git::current_branch().unwrap_or_default();
+        let text = format!("Commit {} on {}: {}", &head.hash[..head.hash.len().min(8)], branch, head.subject);
+        out.push(task_create_comment(cfg, &task_id, &text)?);
+    }
+    // the first commit of a repository has nothing to measure from
+    if let (true, Some(parent)) = (hooks.book_commits, commits.get(1)) {
+        let duration = (head.time - parent.time).min(Duration::minutes(hooks.max_book_minutes));
+        if duration >= Duration::minutes(1) {
+            let tags = entry_tags(cfg, &[]);
+            let label = format!("for task {}{}", task_id, fmt_entry_meta(&tags, cfg.default_billable));
+            let body = NewTimeEntry {
+                start: head.time - duration,
+                duration,
+                tid: Some(task_id.clone()),
+                description: Some(head.subject.clone()),
+                tags,
+                billable: cfg.default_billable,
+            };
+            out.push(book(cfg, body, label, false)?);
+        }
+    }
+    Ok(out.join("\n"))
 }
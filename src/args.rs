@@ -1,11 +1,14 @@
 use std::fmt;
 use std::num::ParseIntError;
 
-use crate::utils::display::ERROR;
+use chrono::NaiveDate;
+use cupcli::pomodoro::Plan;
+use crate::display::ERROR;
 
 pub enum TimeGet {
     Today,
     Week,
+    Month,
     Yesterday,
 }
 
@@ -14,6 +17,18 @@ pub enum TaskGet {
     Sprint,
 }
 
+#[derive(Debug)]
+pub struct TaskSearch {
+    pub query: String,
+    pub flags: Vec<TaskSearchFlag>,
+}
+
+#[derive(Debug)]
+pub enum TaskSearchFlag {
+    Description,
+    Limit(usize),
+}
+
 #[derive(Debug)]
 pub struct TimeTrack<'a> {
     pub mode: TimeTrackMode<'a>,
@@ -23,7 +38,10 @@ pub struct TimeTrack<'a> {
 #[derive(Debug)]
 pub enum TimeTrackFlag<'a> {
     Description(&'a str),
-    Duration(u32)
+    Duration(u32),
+    Offline,
+    Tag(&'a str),
+    Billable(bool),
 }
 
 #[derive(Debug, PartialEq)]
@@ -31,6 +49,53 @@ pub enum TimeTrackMode<'a> {
     Last,
     Free,
     TaskId(&'a str),
+    // the task id in the name of the checked out git branch
+    Branch,
+}
+
+pub enum TagCmd<'a> {
+    List,
+    Create { name: &'a str, fg: Option<&'a str>, bg: Option<&'a str>, entry_ids: Vec<&'a str> },
+    Rename { name: &'a str, new_name: &'a str },
+    Delete { name: &'a str, from: TagScope<'a> },
+}
+
+// the entries `tag delete` removes a tag from
+pub enum TagScope<'a> {
+    Entries(Vec<&'a str>),
+    // every entry of the user starting on or after the day that carries the tag
+    Since(NaiveDate),
+}
+
+pub struct Balance {
+    pub since: Option<NaiveDate>,
+    pub view: BalanceView,
+}
+
+pub enum BalanceView {
+    Days,
+    Weeks,
+    Months,
+}
+
+pub enum PomodoroCmd<'a> {
+    // a task id or `last`, the plan is the cfg one with the command line overrides
+    Start { task: &'a str, plan: Plan },
+    Resume,
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatusbarFormat {
+    Waybar,
+    I3blocks,
+    Polybar,
+}
+
+pub enum VacationCmd {
+    Add(NaiveDate, NaiveDate),
+    List,
+    Remove(NaiveDate),
 }
 
 #[derive(Debug, Clone)]
@@ -43,7 +108,7 @@ impl std::error::Error for ArgError {}
 
 impl fmt::Display for ArgError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut prefix = String::from(format!("{} [ARGUMENT ERROR] ", ERROR));
+        let mut prefix = format!("{} [ARGUMENT ERROR] ", ERROR);
         match self {
             ArgError::ArgCount(msg) => {
                 prefix.push_str("Invalid number of arguments: ");
@@ -61,4 +126,9 @@ impl From<ParseIntError> for ArgError {
     fn from(e: ParseIntError) -> Self {
         ArgError::ArgValue(e.to_string())
     }
-}
\ No newline at end of file
+}
+impl From<chrono::ParseError> for ArgError {
+    fn from(e: chrono::ParseError) -> Self {
+        ArgError::ArgValue(format!("{}, dates are expected as YYYY-MM-DD", e))
+    }
+}
@@ -0,0 +1,199 @@
+//! On-disk cache of ClickUp resources under `$XDG_CACHE_HOME/cupcli`, one TTL per kind.
+
+use std::path::PathBuf;
+use std::{env, fs};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::clock::Range;
+use crate::config::Cfg;
+use crate::error::Error;
+use crate::models::{Hierarchy, Member, Status, Task, TimeEntry};
+
+/// How cached accessors treat the disk cache.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheMode {
+    /// serve fresh entries from disk, fetch and store stale ones
+    Normal,
+    /// always fetch, but store the result for later calls
+    Refresh,
+    /// never read nor write the cache
+    Bypass,
+}
+
+/// The kinds of cached resources, each with its own TTL in [`Cfg`].
+#[derive(Debug, Clone, Copy)]
+pub enum CacheKind {
+    Tasks,
+    Statuses,
+    Members,
+    Hierarchy,
+    TimeEntries,
+    /// the running timer, short-lived as the statusbar polls it
+    Timer,
+}
+
+impl CacheKind {
+    pub const ALL: [CacheKind; 6] = [
+        CacheKind::Tasks,
+        CacheKind::Statuses,
+        CacheKind::Members,
+        CacheKind::Hierarchy,
+        CacheKind::TimeEntries,
+        CacheKind::Timer,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CacheKind::Tasks => "tasks",
+            CacheKind::Statuses => "statuses",
+            CacheKind::Members => "members",
+            CacheKind::Hierarchy => "hierarchy",
+            CacheKind::TimeEntries => "entries",
+            CacheKind::Timer => "timer",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<CacheKind> {
+        CacheKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    fn ttl_mins(&self, cfg: &Cfg) -> u64 {
+        match self {
+            CacheKind::Tasks => cfg.ttl_tasks,
+            CacheKind::Statuses => cfg.ttl_statuses,
+            CacheKind::Members => cfg.ttl_members,
+            CacheKind::Hierarchy => cfg.ttl_hierarchy,
+            CacheKind::TimeEntries => cfg.ttl_entries,
+            CacheKind::Timer => cfg.ttl_timer,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct CacheEntry<T> {
+    fetched_at: i64,
+    data: T,
+}
+
+/// `$XDG_CACHE_HOME/cupcli`, falling back to `~/.cache/cupcli`.
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = match env::var("XDG_CACHE_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".cache"),
+    };
+    Some(base.join("cupcli"))
+}
+
+fn entry_path(kind: CacheKind, key: &str) -> Option<PathBuf> {
+    Some(cache_dir()?.join(kind.name()).join(format!("{}.json", key)))
+}
+
+// the cache is best effort: a missing, stale or corrupt file just means we fetch again
+fn load<T: DeserializeOwned>(cfg: &Cfg, kind: CacheKind, key: &str) -> Option<T> {
+    let raw = fs::read_to_string(entry_path(kind, key)?).ok()?;
+    let entry: CacheEntry<T> = serde_json::from_str(&raw).ok()?;
+    let age = Utc::now().timestamp_millis() - entry.fetched_at;
+    if age < kind.ttl_mins(cfg) as i64 * 60 * 1000 {
+        Some(entry.data)
+    } else {
+        None
+    }
+}
+
+fn store<T: Serialize>(kind: CacheKind, key: &str, data: &T) {
+    let Some(path) = entry_path(kind, key) else { return };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let entry = CacheEntry {
+        fetched_at: Utc::now().timestamp_millis(),
+        data,
+    };
+    if let Ok(raw) = serde_json::to_string(&entry) {
+        let _ = fs::write(path, raw);
+    }
+}
+
+/// Returns the cached value for kind/key or calls `fetch` and caches its result, depending on `cfg.cache_mode`.
+pub fn cached<T, E, F>(cfg: &Cfg, kind: CacheKind, key: &str, fetch: F) -> Result<T, E>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Result<T, E>,
+{
+    if cfg.cache_mode == CacheMode::Normal {
+        if let Some(data) = load(cfg, kind, key) {
+            return Ok(data);
+        }
+    }
+    let data = fetch()?;
+    if cfg.cache_mode != CacheMode::Bypass {
+        store(kind, key, &data);
+    }
+    Ok(data)
+}
+
+/// Drops everything cached for one kind, e.g. after a write that makes it outdated.
+pub fn invalidate(kind: CacheKind) {
+    if let Some(dir) = cache_dir() {
+        let _ = fs::remove_dir_all(dir.join(kind.name()));
+    }
+}
+
+/// Removes the cached kinds, all of them if `kinds` is empty, and returns the cache directory.
+pub fn clear(kinds: &[CacheKind]) -> Option<PathBuf> {
+    let dir = cache_dir()?;
+    if kinds.is_empty() {
+        let _ = fs::remove_dir_all(&dir);
+    }
+    for kind in kinds {
+        invalidate(*kind);
+    }
+    Some(dir)
+}
+
+/// All open tasks of the team.
+pub fn team_tasks(cfg: &Cfg) -> Result<Vec<Task>, Error> {
+    cached(cfg, CacheKind::Tasks, &cfg.team_id, || Client::new(cfg).team_tasks())
+}
+
+/// The open tasks of a list, only those assigned to `assignee` if given.
+pub fn list_tasks(cfg: &Cfg, list_id: &str, assignee: Option<&str>) -> Result<Vec<Task>, Error> {
+    let key = format!("list-{}-{}", list_id, assignee.unwrap_or("all"));
+    cached(cfg, CacheKind::Tasks, &key, || Client::new(cfg).list_tasks(list_id, assignee))
+}
+
+/// The statuses of a list.
+pub fn list_statuses(cfg: &Cfg, list_id: &str) -> Result<Vec<Status>, Error> {
+    cached(cfg, CacheKind::Statuses, list_id, || Client::new(cfg).list_statuses(list_id))
+}
+
+/// The members of the configured team.
+pub fn members(cfg: &Cfg) -> Result<Vec<Member>, Error> {
+    cached(cfg, CacheKind::Members, &cfg.team_id, || Client::new(cfg).members())
+}
+
+/// The space/folder/list tree of the configured team.
+pub fn hierarchy(cfg: &Cfg) -> Result<Hierarchy, Error> {
+    cached(cfg, CacheKind::Hierarchy, &cfg.team_id, || Client::new(cfg).hierarchy())
+}
+
+/// The time entries of one day, including those reaching into it from the day before.
+pub fn day_entries(cfg: &Cfg, day: NaiveDate) -> Result<Vec<TimeEntry>, Error> {
+    let key = format!("day-{}", day);
+    cached(cfg, CacheKind::TimeEntries, &key, || Client::new(cfg).time_entries_overlapping(&Range::day(cfg.zone, day)))
+}
+
+/// The running timer, None if no timer is running.
+pub fn current_timer(cfg: &Cfg) -> Result<Option<TimeEntry>, Error> {
+    cached(cfg, CacheKind::Timer, "current", || Client::new(cfg).current_timer())
+}
+
+/// The most recent time entry since `since`, None if nothing was tracked.
+pub fn last_entry(cfg: &Cfg, since: DateTime<Utc>) -> Result<Option<TimeEntry>, Error> {
+    let key = format!("last-{}", since.timestamp_millis());
+    cached(cfg, CacheKind::TimeEntries, &key, || Client::new(cfg).last_time_entry(since))
+}
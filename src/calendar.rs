@@ -0,0 +1,463 @@
+//! Days without a quota: public holidays, from a local `.ics` file or bundled per-country rules,
+//! and vacations stored under [`data_dir`].
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{Datelike, Days, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::config::data_dir;
+
+/// An all-day event of an `.ics` calendar, optionally repeating every year.
+#[derive(Debug, Clone)]
+pub struct IcsEvent {
+    pub summary: String,
+    pub first: NaiveDate,
+    /// inclusive
+    pub last: NaiveDate,
+    pub yearly: bool,
+}
+
+impl IcsEvent {
+    fn covers(&self, day: NaiveDate) -> bool {
+        if !self.yearly {
+            return self.first <= day && day <= self.last;
+        }
+        if day < self.first {
+            return false;
+        }
+        // compare month and day only, so an event on Feb 29 only matches in leap years
+        let span = (self.last - self.first).num_days();
+        (0..=span).any(|offset| {
+            let date = self.first + Days::new(offset as u64);
+            date.month() == day.month() && date.day() == day.day()
+        })
+    }
+}
+
+/// Where public holidays come from, set by the `holidays` cfg key.
+#[derive(Debug, Clone, Default)]
+pub enum Holidays {
+    #[default]
+    None,
+    /// bundled rules of a country, optionally with the holidays of one of its regions
+    Rules { country: String, region: Option<String> },
+    Ics { path: PathBuf, events: Vec<IcsEvent> },
+}
+
+/// A vacation, both days inclusive.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Vacation {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+/// Holidays and vacations, the days [`crate::Cfg::quota`] counts as off.
+#[derive(Debug, Clone, Default)]
+pub struct DaysOff {
+    pub holidays: Holidays,
+    pub vacations: Vec<Vacation>,
+}
+
+impl DaysOff {
+    /// Loads the holidays named by the `holidays` cfg value, a path to an `.ics` file or a
+    /// country code like `de` or `de-by`, and the stored vacations.
+    pub fn load(holidays: Option<&str>) -> Result<DaysOff, String> {
+        let holidays = match holidays.map(str::trim).filter(|h| !h.is_empty()) {
+            None => Holidays::None,
+            Some(value) if value.ends_with(".ics") || value.contains('/') => {
+                let path = expand_home(value);
+                let raw = fs::read_to_string(&path).map_err(|e| format!("could not read {}: {}", value, e))?;
+                Holidays::Ics { path, events: parse_ics(&raw) }
+            }
+            Some(value) => {
+                let value = value.to_lowercase();
+                let (country, region) = match value.split_once('-') {
+                    Some((country, region)) => (country.to_string(), Some(region.to_string())),
+                    None => (value, None),
+                };
+                if !COUNTRIES.contains(&country.as_str()) {
+                    return Err(format!("no bundled holidays for `{}`, known are {}", country, COUNTRIES.join(", ")));
+                }
+                Holidays::Rules { country, region }
+            }
+        };
+        Ok(DaysOff {
+            holidays,
+            vacations: vacations(),
+        })
+    }
+
+    /// The name of the public holiday on `day`, if any.
+    pub fn holiday(&self, day: NaiveDate) -> Option<String> {
+        match &self.holidays {
+            Holidays::None => None,
+            Holidays::Rules { country, region } => rule_holidays(country, region.as_deref(), day.year())
+                .into_iter()
+                .find(|(date, _)| *date == day)
+                .map(|(_, name)| name.to_string()),
+            Holidays::Ics { events, .. } => events.iter().find(|e| e.covers(day)).map(|e| e.summary.clone()),
+        }
+    }
+
+    /// The files the days off were loaded from, to notice when they change.
+    pub fn sources(&self) -> Vec<PathBuf> {
+        let ics = match &self.holidays {
+            Holidays::Ics { path, .. } => Some(path.clone()),
+            _ => None,
+        };
+        vacations_path().into_iter().chain(ics).collect()
+    }
+
+    pub fn is_vacation(&self, day: NaiveDate) -> bool {
+        self.vacations.iter().any(|v| v.from <= day && day <= v.to)
+    }
+
+    pub fn is_off(&self, day: NaiveDate) -> bool {
+        self.is_vacation(day) || self.holiday(day).is_some()
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+// DTSTART;VALUE=DATE:20241225 or DTSTART:20241225T000000Z, only the date matters
+fn ics_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()
+}
+
+// a VEVENT while its properties are read; the end date carries whether it had a time
+#[derive(Default)]
+struct PartialEvent {
+    start: Option<NaiveDate>,
+    end: Option<(NaiveDate, bool)>,
+    summary: String,
+    yearly: bool,
+}
+
+/// Reads the all-day events of an `.ics` calendar; yearly recurrence is the only RRULE understood.
+pub fn parse_ics(raw: &str) -> Vec<IcsEvent> {
+    // long lines are folded onto continuation lines starting with a space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in raw.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    let mut events = Vec::new();
+    let mut current: Option<PartialEvent> = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        let key = name.split(';').next().unwrap_or_default();
+        match (key, current.as_mut()) {
+            ("BEGIN", _) if value == "VEVENT" => current = Some(PartialEvent::default()),
+            ("END", Some(_)) if value == "VEVENT" => {
+                let PartialEvent { start: Some(first), end, summary, yearly } = current.take().unwrap() else { continue };
+                let last = match end {
+                    // a date-only DTEND is exclusive, a date-time one lies within the last day
+                    Some((end, false)) if end > first => end - Days::new(1),
+                    Some((end, true)) if end >= first => end,
+                    _ => first,
+                };
+                events.push(IcsEvent { summary, first, last, yearly });
+            }
+            ("DTSTART", Some(event)) => event.start = ics_date(value),
+            ("DTEND", Some(event)) => event.end = ics_date(value).map(|date| (date, value.contains('T'))),
+            ("SUMMARY", Some(event)) => event.summary = value.replace("\\,", ","),
+            ("RRULE", Some(event)) => event.yearly = value.split(';').any(|part| part == "FREQ=YEARLY"),
+            _ => {}
+        }
+    }
+    events
+}
+
+const COUNTRIES: [&str; 4] = ["de", "at", "us", "gb"];
+
+// Easter Sunday, anonymous Gregorian algorithm
+fn easter(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).unwrap()
+}
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n).unwrap()
+}
+
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, 5).unwrap_or_else(|| nth_weekday(year, month, weekday, 4))
+}
+
+fn is_weekend(day: NaiveDate) -> bool {
+    matches!(day.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+/// The public holidays of a bundled country (and region) in `year`.
+pub fn rule_holidays(country: &str, region: Option<&str>, year: i32) -> Vec<(NaiveDate, &'static str)> {
+    let easter = easter(year);
+    let in_region = |regions: &[&str]| region.is_some_and(|r| regions.contains(&r));
+    let mut days = Vec::new();
+    match country {
+        "de" => {
+            days.extend([
+                (date(year, 1, 1), "Neujahr"),
+                (easter - Days::new(2), "Karfreitag"),
+                (easter + Days::new(1), "Ostermontag"),
+                (date(year, 5, 1), "Tag der Arbeit"),
+                (easter + Days::new(39), "Christi Himmelfahrt"),
+                (easter + Days::new(50), "Pfingstmontag"),
+                (date(year, 10, 3), "Tag der Deutschen Einheit"),
+                (date(year, 12, 25), "1. Weihnachtstag"),
+                (date(year, 12, 26), "2. Weihnachtstag"),
+            ]);
+            if in_region(&["bw", "by", "st"]) {
+                days.push((date(year, 1, 6), "Heilige Drei Könige"));
+            }
+            if in_region(&["be", "mv"]) {
+                days.push((date(year, 3, 8), "Internationaler Frauentag"));
+            }
+            if in_region(&["bw", "by", "he", "nw", "rp", "sl"]) {
+                days.push((easter + Days::new(60), "Fronleichnam"));
+            }
+            if in_region(&["sl"]) {
+                days.push((date(year, 8, 15), "Mariä Himmelfahrt"));
+            }
+            if in_region(&["th"]) {
+                days.push((date(year, 9, 20), "Weltkindertag"));
+            }
+            if in_region(&["bb", "hb", "hh", "mv", "ni", "sn", "st", "sh", "th"]) {
+                days.push((date(year, 10, 31), "Reformationstag"));
+            }
+            if in_region(&["bw", "by", "nw", "rp", "sl"]) {
+                days.push((date(year, 11, 1), "Allerheiligen"));
+            }
+            if in_region(&["sn"]) {
+                // the Wednesday before November 23rd
+                let mut day = date(year, 11, 22);
+                while day.weekday() != Weekday::Wed {
+                    day = day.pred_opt().unwrap();
+                }
+                days.push((day, "Buß- und Bettag"));
+            }
+        }
+        "at" => days.extend([
+            (date(year, 1, 1), "Neujahr"),
+            (date(year, 1, 6), "Heilige Drei Könige"),
+            (easter + Days::new(1), "Ostermontag"),
+            (date(year, 5, 1), "Staatsfeiertag"),
+            (easter + Days::new(39), "Christi Himmelfahrt"),
+            (easter + Days::new(50), "Pfingstmontag"),
+            (easter + Days::new(60), "Fronleichnam"),
+            (date(year, 8, 15), "Mariä Himmelfahrt"),
+            (date(year, 10, 26), "Nationalfeiertag"),
+            (date(year, 11, 1), "Allerheiligen"),
+            (date(year, 12, 8), "Mariä Empfängnis"),
+            (date(year, 12, 25), "Christtag"),
+            (date(year, 12, 26), "Stefanitag"),
+        ]),
+        "us" => {
+            // fixed federal holidays on a weekend are observed on the closest weekday
+            let observed = |day: NaiveDate| match day.weekday() {
+                Weekday::Sat => day - Days::new(1),
+                Weekday::Sun => day + Days::new(1),
+                _ => day,
+            };
+            // New Year's Day on a Saturday is observed on December 31st of the year before
+            let new_year = [observed(date(year, 1, 1)), observed(date(year + 1, 1, 1))];
+            days.extend(new_year.into_iter().filter(|day| day.year() == year).map(|day| (day, "New Year's Day")));
+            days.extend([
+                (nth_weekday(year, 1, Weekday::Mon, 3), "Martin Luther King Jr. Day"),
+                (nth_weekday(year, 2, Weekday::Mon, 3), "Washington's Birthday"),
+                (last_weekday(year, 5, Weekday::Mon), "Memorial Day"),
+                (observed(date(year, 6, 19)), "Juneteenth"),
+                (observed(date(year, 7, 4)), "Independence Day"),
+                (nth_weekday(year, 9, Weekday::Mon, 1), "Labor Day"),
+                (nth_weekday(year, 10, Weekday::Mon, 2), "Columbus Day"),
+                (observed(date(year, 11, 11)), "Veterans Day"),
+                (nth_weekday(year, 11, Weekday::Thu, 4), "Thanksgiving Day"),
+                (observed(date(year, 12, 25)), "Christmas Day"),
+            ]);
+        }
+        "gb" => {
+            // England and Wales; fixed bank holidays on a weekend move to the next free weekday
+            let mut fixed = vec![(date(year, 1, 1), "New Year's Day"), (date(year, 12, 25), "Christmas Day"), (date(year, 12, 26), "Boxing Day")];
+            days.extend([
+                (easter - Days::new(2), "Good Friday"),
+                (easter + Days::new(1), "Easter Monday"),
+                (nth_weekday(year, 5, Weekday::Mon, 1), "Early May bank holiday"),
+                (last_weekday(year, 5, Weekday::Mon), "Spring bank holiday"),
+                (last_weekday(year, 8, Weekday::Mon), "Summer bank holiday"),
+            ]);
+            for (day, name) in fixed.drain(..) {
+                let mut observed = day;
+                while is_weekend(observed) || days.iter().any(|(d, _)| *d == observed) {
+                    observed = observed + Days::new(1);
+                }
+                days.push((observed, name));
+            }
+        }
+        _ => {}
+    }
+    days
+}
+
+fn vacations_path() -> Option<PathBuf> {
+    Some(data_dir()?.join("vacations.json"))
+}
+
+/// The stored vacations, ordered by start.
+pub fn vacations() -> Vec<Vacation> {
+    vacations_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_vacations(vacations: &[Vacation]) -> std::io::Result<()> {
+    let path = vacations_path().ok_or(std::io::ErrorKind::NotFound)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(vacations)?)
+}
+
+/// Stores a vacation from `from` to `to`, both inclusive.
+pub fn add_vacation(from: NaiveDate, to: NaiveDate) -> std::io::Result<()> {
+    let mut vacations = vacations();
+    vacations.push(Vacation { from, to });
+    vacations.sort_by_key(|v| v.from);
+    save_vacations(&vacations)
+}
+
+/// Removes the vacations starting on `from` and returns how many there were.
+pub fn remove_vacation(from: NaiveDate) -> std::io::Result<usize> {
+    let mut vacations = vacations();
+    let before = vacations.len();
+    vacations.retain(|v| v.from != from);
+    save_vacations(&vacations)?;
+    Ok(before - vacations.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holiday(country: &str, region: Option<&str>, day: NaiveDate) -> Option<&'static str> {
+        rule_holidays(country, region, day.year()).into_iter().find(|(d, _)| *d == day).map(|(_, name)| name)
+    }
+
+    #[test]
+    fn easter_of_known_years() {
+        assert_eq!(easter(2000), date(2000, 4, 23));
+        assert_eq!(easter(2019), date(2019, 4, 21));
+        assert_eq!(easter(2024), date(2024, 3, 31));
+        assert_eq!(easter(2025), date(2025, 4, 20));
+        assert_eq!(easter(2038), date(2038, 4, 25));
+    }
+
+    #[test]
+    fn easter_based_holidays() {
+        assert_eq!(holiday("de", None, date(2025, 4, 18)), Some("Karfreitag"));
+        assert_eq!(holiday("de", None, date(2025, 4, 21)), Some("Ostermontag"));
+        assert_eq!(holiday("de", None, date(2025, 5, 29)), Some("Christi Himmelfahrt"));
+        assert_eq!(holiday("de", None, date(2025, 6, 9)), Some("Pfingstmontag"));
+        assert_eq!(holiday("gb", None, date(2024, 3, 29)), Some("Good Friday"));
+    }
+
+    #[test]
+    fn regional_holidays_need_the_region() {
+        assert_eq!(holiday("de", None, date(2025, 6, 19)), None);
+        assert_eq!(holiday("de", Some("by"), date(2025, 6, 19)), Some("Fronleichnam"));
+        assert_eq!(holiday("de", Some("sn"), date(2024, 11, 20)), Some("Buß- und Bettag"));
+        assert_eq!(holiday("de", Some("sn"), date(2025, 11, 19)), Some("Buß- und Bettag"));
+    }
+
+    #[test]
+    fn weekend_holidays_are_observed_on_weekdays() {
+        // July 4th 2021 was a Sunday
+        assert_eq!(holiday("us", None, date(2021, 7, 5)), Some("Independence Day"));
+        // Christmas 2021 was a Saturday, Boxing Day a Sunday
+        assert_eq!(holiday("gb", None, date(2021, 12, 27)), Some("Christmas Day"));
+        assert_eq!(holiday("gb", None, date(2021, 12, 28)), Some("Boxing Day"));
+        assert_eq!(holiday("us", None, date(2025, 11, 27)), Some("Thanksgiving Day"));
+        assert_eq!(holiday("us", None, date(2025, 5, 26)), Some("Memorial Day"));
+    }
+
+    #[test]
+    fn new_year_on_a_saturday_is_observed_the_year_before() {
+        // January 1st 2022 was a Saturday
+        assert_eq!(holiday("us", None, date(2021, 12, 31)), Some("New Year's Day"));
+        assert_eq!(holiday("us", None, date(2022, 1, 1)), None);
+        assert_eq!(rule_holidays("us", None, 2022).iter().filter(|(_, name)| *name == "New Year's Day").count(), 0);
+        // and on the Monday after a Sunday, January 1st 2023
+        assert_eq!(holiday("us", None, date(2023, 1, 2)), Some("New Year's Day"));
+    }
+
+    #[test]
+    fn ics_all_day_events() {
+        let raw = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;VALUE=DATE:20241224\r\n\
+            DTEND;VALUE=DATE:20241227\r\n\
+            SUMMARY:Christmas\\, office closed\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;VALUE=DATE:20200229\r\n\
+            RRULE:FREQ=YEARLY\r\n\
+            SUMMARY:Leap\r\n \
+            day\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART:20240501T080000Z\r\n\
+            DTEND:20240501T170000Z\r\n\
+            SUMMARY:Timed\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let events = parse_ics(raw);
+        assert_eq!(events.len(), 3);
+        // a date-only DTEND is exclusive
+        assert_eq!((events[0].first, events[0].last), (date(2024, 12, 24), date(2024, 12, 26)));
+        assert_eq!(events[0].summary, "Christmas, office closed");
+        assert_eq!(events[1].summary, "Leapday");
+        assert!(events[1].yearly);
+        assert_eq!((events[2].first, events[2].last), (date(2024, 5, 1), date(2024, 5, 1)));
+    }
+
+    #[test]
+    fn yearly_events_repeat_on_their_day_only() {
+        let days_off = DaysOff {
+            holidays: Holidays::Ics {
+                path: PathBuf::new(),
+                events: vec![IcsEvent { summary: "Leap".to_string(), first: date(2020, 2, 29), last: date(2020, 2, 29), yearly: true }],
+            },
+            vacations: vec![Vacation { from: date(2024, 8, 5), to: date(2024, 8, 9) }],
+        };
+        assert!(days_off.holiday(date(2024, 2, 29)).is_some());
+        assert!(days_off.holiday(date(2023, 2, 28)).is_none());
+        assert!(days_off.holiday(date(2016, 2, 29)).is_none());
+        assert!(days_off.is_off(date(2024, 8, 9)));
+        assert!(!days_off.is_off(date(2024, 8, 10)));
+    }
+}
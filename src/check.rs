@@ -0,0 +1,185 @@
+//! Consistency checks of booked time: overlapping entries, gaps within the working hours,
+//! entries without a task and suspiciously long ones.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::clock::Range;
+use crate::config::Cfg;
+use crate::models::TimeEntry;
+
+/// Something off in the booked time of a day.
+#[derive(Debug, Clone, Copy)]
+pub enum Issue<'a> {
+    /// `later` starts before `earlier` ends
+    Overlap { earlier: &'a TimeEntry, later: &'a TimeEntry },
+    /// nothing booked from `start` to `end` within the working hours; `before` is the last entry before the gap
+    Gap { start: DateTime<Utc>, end: DateTime<Utc>, before: Option<&'a TimeEntry> },
+    NoTask(&'a TimeEntry),
+    /// longer than `long_entry` hours, e.g. a forgotten timer
+    Long(&'a TimeEntry),
+}
+
+impl Issue<'_> {
+    /// Where the issue starts, issues are reported in this order.
+    pub fn start(&self) -> DateTime<Utc> {
+        match self {
+            Issue::Overlap { later, .. } => later.start,
+            Issue::Gap { start, .. } => *start,
+            Issue::NoTask(entry) | Issue::Long(entry) => entry.start,
+        }
+    }
+}
+
+fn overlaps<'a>(entries: &[&'a TimeEntry], range: &Range) -> Vec<Issue<'a>> {
+    let mut issues = Vec::new();
+    // the entry reaching furthest so far, every entry starting before its end overlaps it
+    let mut furthest: Option<&TimeEntry> = None;
+    for &entry in entries {
+        if let Some(earlier) = furthest {
+            if entry.start < earlier.end_or_now() && range.contains(entry.start) {
+                issues.push(Issue::Overlap { earlier, later: entry });
+            }
+        }
+        if furthest.is_none_or(|f| entry.end_or_now() > f.end_or_now()) {
+            furthest = Some(entry);
+        }
+    }
+    issues
+}
+
+fn gaps<'a>(cfg: &Cfg, entries: &[&'a TimeEntry], day: NaiveDate) -> Vec<Issue<'a>> {
+    let threshold = Duration::minutes(cfg.gap_threshold_mins);
+    let start = cfg.zone.at(day, cfg.work_start);
+    // the rest of today isn't a gap yet
+    let end = cfg.zone.at(day, cfg.work_end).min(Utc::now());
+    let mut issues = Vec::new();
+    let mut cursor = start;
+    let mut before: Option<&TimeEntry> = None;
+    for &entry in entries {
+        if entry.start >= end {
+            break;
+        }
+        if entry.start - cursor >= threshold {
+            issues.push(Issue::Gap {
+                start: cursor,
+                end: entry.start,
+                before,
+            });
+        }
+        cursor = cursor.max(entry.end_or_now());
+        before = Some(entry);
+    }
+    if end - cursor >= threshold {
+        issues.push(Issue::Gap { start: cursor, end, before });
+    }
+    issues
+}
+
+/// Checks the entries of the days from `first` to `last`, both inclusive. `entries` should
+/// include those reaching into the days, see [`crate::Client::time_entries_overlapping`].
+pub fn check<'a>(cfg: &Cfg, entries: &'a [TimeEntry], first: NaiveDate, last: NaiveDate) -> Vec<Issue<'a>> {
+    let range = Range::days(cfg.zone, first, last);
+    let mut sorted: Vec<&TimeEntry> = entries.iter().collect();
+    sorted.sort_by_key(|entry| entry.start);
+    let mut issues = overlaps(&sorted, &range);
+    for &entry in sorted.iter().filter(|entry| range.contains(entry.start)) {
+        if entry.task.is_none() {
+            issues.push(Issue::NoTask(entry));
+        }
+        if entry.elapsed() > Duration::hours(cfg.long_entry_hours) {
+            issues.push(Issue::Long(entry));
+        }
+    }
+    issues.extend(gaps_between(cfg, entries, first, last));
+    issues.sort_by_key(Issue::start);
+    issues
+}
+
+/// Only the [`Issue::Gap`]s of the days from `first` to `last`, in order.
+pub fn gaps_between<'a>(cfg: &Cfg, entries: &'a [TimeEntry], first: NaiveDate, last: NaiveDate) -> Vec<Issue<'a>> {
+    let mut sorted: Vec<&TimeEntry> = entries.iter().collect();
+    sorted.sort_by_key(|entry| entry.start);
+    // only working days have working hours
+    first
+        .iter_days()
+        .take_while(|day| *day <= last)
+        .filter(|day| cfg.quota(*day) > 0.0)
+        .flat_map(|day| gaps(cfg, &sorted, day))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> Cfg {
+        let pairs = [("timezone", "UTC"), ("working_hours", "09:00-17:00"), ("gap_threshold", "15")];
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    // an entry on 2024-05-01, a Wednesday, from and to the given UTC times
+    fn entry(id: &str, start: &str, end: &str, task: bool) -> TimeEntry {
+        let at = |time: &str| format!("2024-05-01T{}:00Z", time).parse::<DateTime<Utc>>().unwrap();
+        let mut raw = serde_json::json!({
+            "id": id,
+            "start": at(start).timestamp_millis(),
+            "end": at(end).timestamp_millis(),
+            "duration": (at(end) - at(start)).num_milliseconds(),
+        });
+        if task {
+            raw["task"] = serde_json::json!({"id": "t1", "name": "Task", "status": {"status": "open"}});
+        }
+        serde_json::from_value(raw).unwrap()
+    }
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+    }
+
+    fn ids(issue: &Issue) -> String {
+        match issue {
+            Issue::Overlap { earlier, later } => format!("overlap {} {}", earlier.id, later.id),
+            Issue::Gap { start, end, .. } => format!("gap {} {}", start.format("%H:%M"), end.format("%H:%M")),
+            Issue::NoTask(entry) => format!("no task {}", entry.id),
+            Issue::Long(entry) => format!("long {}", entry.id),
+        }
+    }
+
+    fn gaps(entries: &[TimeEntry], first: NaiveDate, last: NaiveDate) -> Vec<String> {
+        gaps_between(&cfg(), entries, first, last).iter().map(ids).collect()
+    }
+
+    #[test]
+    fn overlap_gap_and_missing_task_in_order() {
+        let entries = [entry("a", "09:00", "12:00", true), entry("b", "11:30", "13:00", true), entry("c", "14:00", "17:00", false)];
+        let issues: Vec<String> = check(&cfg(), &entries, day(), day()).iter().map(ids).collect();
+        assert_eq!(issues, ["overlap a b", "gap 13:00 14:00", "no task c"]);
+    }
+
+    #[test]
+    fn later_entries_overlap_the_one_reaching_furthest() {
+        let entries = [entry("long", "09:00", "17:00", true), entry("b", "10:00", "11:00", true), entry("c", "12:00", "13:00", true)];
+        let issues: Vec<String> = check(&cfg(), &entries, day(), day()).iter().map(ids).collect();
+        assert_eq!(issues, ["overlap long b", "overlap long c"]);
+    }
+
+    #[test]
+    fn gaps_below_the_threshold_and_outside_working_hours_are_fine() {
+        let entries = [entry("a", "08:00", "12:00", true), entry("b", "12:10", "16:50", true)];
+        assert!(gaps(&entries, day(), day()).is_empty());
+    }
+
+    #[test]
+    fn unbooked_edges_of_the_day_are_gaps() {
+        let entries = [entry("a", "10:00", "16:00", true)];
+        assert_eq!(gaps(&entries, day(), day()), ["gap 09:00 10:00", "gap 16:00 17:00"]);
+    }
+
+    #[test]
+    fn days_off_have_no_gaps() {
+        // 2024-05-04 is a Saturday
+        let saturday = NaiveDate::from_ymd_opt(2024, 5, 4).unwrap();
+        assert!(gaps(&[], saturday, saturday).is_empty());
+        assert_eq!(gaps(&[], day(), day()).len(), 1);
+    }
+}
@@ -0,0 +1,394 @@
+//! Typed access to the ClickUp API.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::clock::Range;
+use crate::config::Cfg;
+use crate::error::Error;
+use crate::models::*;
+use crate::utils::request::{block_on, blocking, bounded, get_json, get_time_entries, pages, post_json, send};
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use reqwest::Method;
+
+/// Base URL of the ClickUp API v2.
+pub const API_URL: &str = "https://api.clickup.com/api/v2";
+
+/// How long before a range an entry may start and still reach into it. ClickUp filters time
+/// entries by their start only, so overlapping queries look back this far.
+pub const MAX_ENTRY_SPAN_HOURS: i64 = 48;
+
+/// Client for the ClickUp endpoints cupcli uses, scoped to the team of the given [`Cfg`].
+///
+/// Every call goes to the network through the shared request layer (timeouts, retries,
+/// rate limiting). Use the accessors in [`crate::cache`] where slightly stale data is fine.
+/// This is a blocking facade over [`AsyncClient`].
+///
+/// ```no_run
+/// let cfg = cupcli::config::build_cfg()?;
+/// let client = cupcli::Client::new(&cfg);
+/// if let Some(timer) = client.current_timer()? {
+///     println!("running since {}", timer.start);
+/// }
+/// # Ok::<(), cupcli::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Client<'a> {
+    inner: AsyncClient<'a>,
+}
+
+impl<'a> Client<'a> {
+    pub fn new(cfg: &'a Cfg) -> Self {
+        Client { inner: AsyncClient::new(cfg) }
+    }
+
+    /// The async client this one wraps, for callers that run their own futures through
+    /// [`crate::utils::request::block_on`].
+    pub fn as_async(&self) -> AsyncClient<'a> {
+        self.inner
+    }
+
+    /// Time entries starting in `[start, end)`, oldest first.
+    /// Long ranges are fetched in windows, so quarter-long reports are complete.
+    pub fn time_entries(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<TimeEntry>, Error> {
+        block_on(self.inner.time_entries(start, end))
+    }
+
+    /// Time entries overlapping `range`, including those that started up to
+    /// [`MAX_ENTRY_SPAN_HOURS`] before it, e.g. night shifts and forgotten timers.
+    pub fn time_entries_overlapping(&self, range: &Range) -> Result<Vec<TimeEntry>, Error> {
+        block_on(self.inner.time_entries_overlapping(range))
+    }
+
+    /// The most recent time entry between `since` and now, if any.
+    pub fn last_time_entry(&self, since: DateTime<Utc>) -> Result<Option<TimeEntry>, Error> {
+        block_on(self.inner.last_time_entry(since))
+    }
+
+    /// Creates a time entry.
+    pub fn create_time_entry(&self, entry: &NewTimeEntry) -> Result<(), Error> {
+        block_on(self.inner.create_time_entry(entry))
+    }
+
+    /// Creates several time entries concurrently, with one result per entry.
+    pub fn create_time_entries(&self, entries: &[NewTimeEntry]) -> Vec<Result<(), Error>> {
+        block_on(self.inner.create_time_entries(entries))
+    }
+
+    /// Moves a time entry to new start and end times.
+    pub fn update_time_entry(&self, entry_id: &str, update: &TimeEntryUpdate) -> Result<(), Error> {
+        block_on(self.inner.update_time_entry(entry_id, update))
+    }
+
+    /// Deletes a time entry.
+    pub fn delete_time_entry(&self, entry_id: &str) -> Result<(), Error> {
+        block_on(self.inner.delete_time_entry(entry_id))
+    }
+
+    /// The currently running timer of the authenticated user.
+    pub fn current_timer(&self) -> Result<Option<TimeEntry>, Error> {
+        block_on(self.inner.current_timer())
+    }
+
+    /// Starts a timer as described by `body`. A running timer is stopped by ClickUp.
+    pub fn start_timer(&self, body: &StartTimer) -> Result<Option<TimeEntry>, Error> {
+        block_on(self.inner.start_timer(body))
+    }
+
+    /// Stops the running timer and returns the resulting entry, None if no timer was running.
+    pub fn stop_timer(&self) -> Result<Option<TimeEntry>, Error> {
+        block_on(self.inner.stop_timer())
+    }
+
+    /// All time entry tags used in the team.
+    pub fn tags(&self) -> Result<Vec<Tag>, Error> {
+        block_on(self.inner.tags())
+    }
+
+    /// Adds tags to time entries, creating tags that don't exist yet.
+    pub fn add_tags(&self, entry_ids: &[String], tags: &[Tag]) -> Result<(), Error> {
+        block_on(self.inner.add_tags(entry_ids, tags))
+    }
+
+    /// Removes tags from time entries; a tag no entry carries anymore is gone.
+    pub fn remove_tags(&self, entry_ids: &[String], tags: &[Tag]) -> Result<(), Error> {
+        block_on(self.inner.remove_tags(entry_ids, tags))
+    }
+
+    /// Renames a tag on all entries carrying it.
+    pub fn rename_tag(&self, rename: &TagRename) -> Result<(), Error> {
+        block_on(self.inner.rename_tag(rename))
+    }
+
+    /// A single task by id.
+    pub fn task(&self, task_id: &str) -> Result<Task, Error> {
+        block_on(self.inner.task(task_id))
+    }
+
+    /// A task by its custom id like `DEV-421`, the returned task carries the ClickUp id.
+    pub fn task_by_custom_id(&self, custom_id: &str) -> Result<Task, Error> {
+        block_on(self.inner.task_by_custom_id(custom_id))
+    }
+
+    /// The open tasks of the team page by page, the next page is only fetched when iterated to.
+    pub fn team_task_pages(&self) -> impl Iterator<Item = Result<Vec<Task>, Error>> + 'a {
+        blocking(self.inner.team_task_pages())
+    }
+
+    /// All open tasks of the team, including subtasks, across all pages.
+    pub fn team_tasks(&self) -> Result<Vec<Task>, Error> {
+        block_on(self.inner.team_tasks())
+    }
+
+    /// The open tasks of a list page by page, optionally only those assigned to `assignee`.
+    pub fn list_task_pages(&self, list_id: &str, assignee: Option<&str>) -> impl Iterator<Item = Result<Vec<Task>, Error>> + 'a {
+        blocking(self.inner.list_task_pages(list_id, assignee))
+    }
+
+    /// All open tasks of a list across all pages, optionally only those assigned to `assignee`.
+    pub fn list_tasks(&self, list_id: &str, assignee: Option<&str>) -> Result<Vec<Task>, Error> {
+        block_on(self.inner.list_tasks(list_id, assignee))
+    }
+
+    /// The open tasks of several lists, fetched concurrently, in the order of `list_ids`.
+    pub fn lists_tasks(&self, list_ids: &[&str], assignee: Option<&str>) -> Result<Vec<Task>, Error> {
+        block_on(self.inner.lists_tasks(list_ids, assignee))
+    }
+
+    /// Sets the status of a task; `status` must be one of the statuses of the task's list.
+    pub fn set_task_status(&self, task_id: &str, status: &str) -> Result<(), Error> {
+        block_on(self.inner.set_task_status(task_id, status))
+    }
+
+    /// The comments of a task page by page, newest first as ClickUp pages them.
+    pub fn comment_pages(&self, task_id: &str) -> impl Iterator<Item = Result<Vec<Comment>, Error>> + 'a {
+        blocking(self.inner.comment_pages(task_id))
+    }
+
+    /// All comments of a task, oldest first.
+    pub fn comments(&self, task_id: &str) -> Result<Vec<Comment>, Error> {
+        block_on(self.inner.comments(task_id))
+    }
+
+    /// Adds a comment to a task without notifying watchers.
+    pub fn create_comment(&self, task_id: &str, text: &str) -> Result<(), Error> {
+        block_on(self.inner.create_comment(task_id, text))
+    }
+
+    /// The statuses configured on a list, in board order.
+    pub fn list_statuses(&self, list_id: &str) -> Result<Vec<Status>, Error> {
+        block_on(self.inner.list_statuses(list_id))
+    }
+
+    /// The members of the configured team.
+    pub fn members(&self) -> Result<Vec<Member>, Error> {
+        block_on(self.inner.members())
+    }
+
+    /// The non-archived spaces of the team with their folders and lists.
+    pub fn hierarchy(&self) -> Result<Hierarchy, Error> {
+        block_on(self.inner.hierarchy())
+    }
+}
+
+/// Async variant of [`Client`]. Independent requests of one call (time entry windows,
+/// the folders and lists of every space, several task lists) run concurrently, at most
+/// `cfg.max_concurrency` at a time.
+#[derive(Debug, Clone, Copy)]
+pub struct AsyncClient<'a> {
+    cfg: &'a Cfg,
+}
+
+impl<'a> AsyncClient<'a> {
+    pub fn new(cfg: &'a Cfg) -> Self {
+        AsyncClient { cfg }
+    }
+
+    fn team_url(&self, path: &str) -> String {
+        format!("{}/team/{}/{}", API_URL, self.cfg.team_id, path)
+    }
+
+    pub async fn time_entries(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<TimeEntry>, Error> {
+        let url = self.team_url("time_entries");
+        Ok(get_time_entries(self.cfg, start.timestamp_millis(), end.timestamp_millis(), &url).await?.data)
+    }
+
+    pub async fn time_entries_overlapping(&self, range: &Range) -> Result<Vec<TimeEntry>, Error> {
+        let entries = self.time_entries(range.start - Duration::hours(MAX_ENTRY_SPAN_HOURS), range.end).await?;
+        Ok(entries.into_iter().filter(|entry| entry.end_or_now() > range.start).collect())
+    }
+
+    pub async fn last_time_entry(&self, since: DateTime<Utc>) -> Result<Option<TimeEntry>, Error> {
+        Ok(self.time_entries(since, Utc::now()).await?.into_iter().last())
+    }
+
+    pub async fn create_time_entry(&self, entry: &NewTimeEntry) -> Result<(), Error> {
+        send(self.cfg, Method::POST, &self.team_url("time_entries"), &[], Some(serde_json::to_string(entry)?)).await?;
+        Ok(())
+    }
+
+    pub async fn create_time_entries(&self, entries: &[NewTimeEntry]) -> Vec<Result<(), Error>> {
+        stream::iter(entries.iter().map(|entry| self.create_time_entry(entry)))
+            .buffered(self.cfg.max_concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    pub async fn update_time_entry(&self, entry_id: &str, update: &TimeEntryUpdate) -> Result<(), Error> {
+        let url = self.team_url(&format!("time_entries/{}", entry_id));
+        send(self.cfg, Method::PUT, &url, &[], Some(serde_json::to_string(update)?)).await?;
+        Ok(())
+    }
+
+    pub async fn delete_time_entry(&self, entry_id: &str) -> Result<(), Error> {
+        send(self.cfg, Method::DELETE, &self.team_url(&format!("time_entries/{}", entry_id)), &[], None).await?;
+        Ok(())
+    }
+
+    pub async fn current_timer(&self) -> Result<Option<TimeEntry>, Error> {
+        let res: TimeEntryResponse = get_json(self.cfg, &self.team_url("time_entries/current"), &[]).await?;
+        Ok(res.data)
+    }
+
+    pub async fn start_timer(&self, body: &StartTimer) -> Result<Option<TimeEntry>, Error> {
+        let res: TimeEntryResponse = post_json(self.cfg, &self.team_url("time_entries/start"), body).await?;
+        Ok(res.data)
+    }
+
+    pub async fn stop_timer(&self) -> Result<Option<TimeEntry>, Error> {
+        if self.current_timer().await?.is_none() {
+            return Ok(None);
+        }
+        let res: TimeEntryResponse = post_json(self.cfg, &self.team_url("time_entries/stop"), &serde_json::json!({})).await?;
+        Ok(res.data)
+    }
+
+    pub async fn tags(&self) -> Result<Vec<Tag>, Error> {
+        let tags: Tags = get_json(self.cfg, &self.team_url("time_entries/tags"), &[]).await?;
+        Ok(tags.data)
+    }
+
+    async fn entry_tags(&self, method: Method, entry_ids: &[String], tags: &[Tag]) -> Result<(), Error> {
+        let body = TimeEntryTags {
+            time_entry_ids: entry_ids.to_vec(),
+            tags: tags.to_vec(),
+        };
+        send(self.cfg, method, &self.team_url("time_entries/tags"), &[], Some(serde_json::to_string(&body)?)).await?;
+        Ok(())
+    }
+
+    pub async fn add_tags(&self, entry_ids: &[String], tags: &[Tag]) -> Result<(), Error> {
+        self.entry_tags(Method::POST, entry_ids, tags).await
+    }
+
+    pub async fn remove_tags(&self, entry_ids: &[String], tags: &[Tag]) -> Result<(), Error> {
+        self.entry_tags(Method::DELETE, entry_ids, tags).await
+    }
+
+    pub async fn rename_tag(&self, rename: &TagRename) -> Result<(), Error> {
+        send(self.cfg, Method::PUT, &self.team_url("time_entries/tags"), &[], Some(serde_json::to_string(rename)?)).await?;
+        Ok(())
+    }
+
+    pub async fn task(&self, task_id: &str) -> Result<Task, Error> {
+        get_json(self.cfg, &format!("{}/task/{}", API_URL, task_id), &[]).await
+    }
+
+    /// A task by its custom id like `DEV-421`, the returned task carries the ClickUp id.
+    pub async fn task_by_custom_id(&self, custom_id: &str) -> Result<Task, Error> {
+        let query = [("custom_task_ids", "true".to_string()), ("team_id", self.cfg.team_id.clone())];
+        get_json(self.cfg, &format!("{}/task/{}", API_URL, custom_id), &query).await
+    }
+
+    pub fn team_task_pages(&self) -> impl Stream<Item = Result<Vec<Task>, Error>> + 'a {
+        let query = vec![("page", "0".to_string()), ("subtasks", "true".to_string())];
+        pages::<Tasks>(self.cfg, self.team_url("task"), query)
+    }
+
+    pub async fn team_tasks(&self) -> Result<Vec<Task>, Error> {
+        self.team_task_pages().try_concat().await
+    }
+
+    pub fn list_task_pages(&self, list_id: &str, assignee: Option<&str>) -> impl Stream<Item = Result<Vec<Task>, Error>> + 'a {
+        let mut query = vec![("page", "0".to_string()), ("subtasks", "true".to_string())];
+        if let Some(assignee) = assignee {
+            query.push(("assignees[]", assignee.to_string()));
+        }
+        pages::<Tasks>(self.cfg, format!("{}/list/{}/task", API_URL, list_id), query)
+    }
+
+    pub async fn list_tasks(&self, list_id: &str, assignee: Option<&str>) -> Result<Vec<Task>, Error> {
+        self.list_task_pages(list_id, assignee).try_concat().await
+    }
+
+    pub async fn lists_tasks(&self, list_ids: &[&str], assignee: Option<&str>) -> Result<Vec<Task>, Error> {
+        let lists = bounded(list_ids.iter().map(|id| self.list_tasks(id, assignee)), self.cfg.max_concurrency).await?;
+        Ok(lists.into_iter().flatten().collect())
+    }
+
+    pub async fn set_task_status(&self, task_id: &str, status: &str) -> Result<(), Error> {
+        let body = TaskUpdate {
+            status: Some(status.to_string()),
+        };
+        send(self.cfg, Method::PUT, &format!("{}/task/{}", API_URL, task_id), &[], Some(serde_json::to_string(&body)?)).await?;
+        Ok(())
+    }
+
+    pub fn comment_pages(&self, task_id: &str) -> impl Stream<Item = Result<Vec<Comment>, Error>> + 'a {
+        pages::<Comments>(self.cfg, format!("{}/task/{}/comment", API_URL, task_id), Vec::new())
+    }
+
+    pub async fn comments(&self, task_id: &str) -> Result<Vec<Comment>, Error> {
+        let mut comments: Vec<Comment> = self.comment_pages(task_id).try_concat().await?;
+        // ClickUp pages newest first
+        comments.reverse();
+        Ok(comments)
+    }
+
+    pub async fn create_comment(&self, task_id: &str, text: &str) -> Result<(), Error> {
+        let body = NewComment {
+            comment_text: text.to_string(),
+            notify_all: false,
+        };
+        let url = format!("{}/task/{}/comment", API_URL, task_id);
+        send(self.cfg, Method::POST, &url, &[], Some(serde_json::to_string(&body)?)).await?;
+        Ok(())
+    }
+
+    pub async fn list_statuses(&self, list_id: &str) -> Result<Vec<Status>, Error> {
+        let list: List = get_json(self.cfg, &format!("{}/list/{}", API_URL, list_id), &[]).await?;
+        Ok(list.statuses)
+    }
+
+    pub async fn members(&self) -> Result<Vec<Member>, Error> {
+        let teams: Teams = get_json(self.cfg, &format!("{}/team", API_URL), &[]).await?;
+        Ok(teams
+            .teams
+            .into_iter()
+            .find(|team| team.id == self.cfg.team_id)
+            .map(|team| team.members.into_iter().map(|m| m.user).collect())
+            .unwrap_or_default())
+    }
+
+    async fn space_node(&self, space: Space) -> Result<SpaceNode, Error> {
+        let archived = [("archived", "false".to_string())];
+        let folders_url = format!("{}/space/{}/folder", API_URL, space.id);
+        let lists_url = format!("{}/space/{}/list", API_URL, space.id);
+        let (folders, lists) = futures::try_join!(
+            get_json::<Folders>(self.cfg, &folders_url, &archived),
+            get_json::<Lists>(self.cfg, &lists_url, &archived),
+        )?;
+        Ok(SpaceNode {
+            id: space.id,
+            name: space.name,
+            folders: folders.folders,
+            lists: lists.lists,
+        })
+    }
+
+    pub async fn hierarchy(&self) -> Result<Hierarchy, Error> {
+        let archived = [("archived", "false".to_string())];
+        let spaces: Spaces = get_json(self.cfg, &self.team_url("space"), &archived).await?;
+        let nodes = bounded(spaces.spaces.into_iter().map(|space| self.space_node(space)), self.cfg.max_concurrency).await?;
+        Ok(Hierarchy { spaces: nodes })
+    }
+}
@@ -0,0 +1,160 @@
+//! Day boundaries in the configured timezone.
+//!
+//! Every day-based report turns dates into half-open `[start, end)` UTC ranges here, so days
+//! start at the first instant of the local day, also on days with a DST change.
+
+use chrono::{DateTime, Days, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+// the longest DST gaps are an hour or two, give up on a day that has no valid time for longer
+const MAX_GAP_MINUTES: i64 = 24 * 60;
+
+/// The timezone days are counted in, the `timezone` cfg key.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Zone {
+    /// the timezone of the system
+    #[default]
+    System,
+    /// an IANA timezone like `Europe/Berlin`
+    Named(Tz),
+}
+
+// the first valid instant at or after a wall clock time: a DST gap can swallow it and a
+// fold makes it ambiguous, the earlier one is meant then
+fn first_instant<T: TimeZone>(tz: &T, local: NaiveDateTime) -> DateTime<Utc> {
+    for minute in 0..=MAX_GAP_MINUTES {
+        match tz.from_local_datetime(&(local + chrono::Duration::minutes(minute))) {
+            LocalResult::Single(t) | LocalResult::Ambiguous(t, _) => return t.with_timezone(&Utc),
+            LocalResult::None => continue,
+        }
+    }
+    // no valid time for a whole day, fall back to treating it as UTC
+    local.and_utc()
+}
+
+impl Zone {
+    /// Parses an IANA name; `local` and `system` select the system timezone.
+    pub fn parse(name: &str) -> Result<Zone, String> {
+        match name.trim() {
+            "" | "local" | "system" => Ok(Zone::System),
+            name => name.parse::<Tz>().map(Zone::Named).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Wall clock time of `t` in this zone.
+    pub fn naive(&self, t: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::System => t.with_timezone(&chrono::Local).naive_local(),
+            Zone::Named(tz) => t.with_timezone(tz).naive_local(),
+        }
+    }
+
+    /// The day `t` falls on in this zone.
+    pub fn date_of(&self, t: DateTime<Utc>) -> NaiveDate {
+        self.naive(t).date()
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.date_of(Utc::now())
+    }
+
+    /// The first instant of `day`.
+    pub fn start_of(&self, day: NaiveDate) -> DateTime<Utc> {
+        self.at(day, NaiveTime::MIN)
+    }
+
+    /// The instant the wall clock shows `time` on `day`, or the first one after if a DST gap skips it.
+    pub fn at(&self, day: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+        match self {
+            Zone::System => first_instant(&chrono::Local, day.and_time(time)),
+            Zone::Named(tz) => first_instant(tz, day.and_time(time)),
+        }
+    }
+}
+
+/// A half-open UTC range `[start, end)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl Range {
+    /// The days from `first` to `last`, both inclusive.
+    pub fn days(zone: Zone, first: NaiveDate, last: NaiveDate) -> Range {
+        Range {
+            start: zone.start_of(first),
+            end: zone.start_of(last + Days::new(1)),
+        }
+    }
+
+    pub fn day(zone: Zone, day: NaiveDate) -> Range {
+        Range::days(zone, day, day)
+    }
+
+    pub fn contains(&self, t: DateTime<Utc>) -> bool {
+        self.start <= t && t < self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(raw: &str) -> DateTime<Utc> {
+        raw.parse().unwrap()
+    }
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn days_are_local_midnights() {
+        let berlin = Zone::parse("Europe/Berlin").unwrap();
+        let range = Range::days(berlin, day(2024, 1, 15), day(2024, 1, 16));
+        assert_eq!(range.start, utc("2024-01-14T23:00:00Z"));
+        assert_eq!(range.end, utc("2024-01-16T23:00:00Z"));
+    }
+
+    #[test]
+    fn dst_days_are_shorter_or_longer() {
+        let berlin = Zone::parse("Europe/Berlin").unwrap();
+        let spring = Range::day(berlin, day(2024, 3, 31));
+        assert_eq!(spring.start, utc("2024-03-30T23:00:00Z"));
+        assert_eq!((spring.end - spring.start).num_hours(), 23);
+        let autumn = Range::day(berlin, day(2024, 10, 27));
+        assert_eq!(autumn.start, utc("2024-10-26T22:00:00Z"));
+        assert_eq!((autumn.end - autumn.start).num_hours(), 25);
+    }
+
+    #[test]
+    fn times_in_a_dst_gap_move_to_its_end() {
+        let berlin = Zone::parse("Europe/Berlin").unwrap();
+        assert_eq!(berlin.at(day(2024, 3, 31), NaiveTime::from_hms_opt(2, 30, 0).unwrap()), utc("2024-03-31T01:00:00Z"));
+        // the earlier of the two 02:30 when the clocks go back
+        assert_eq!(berlin.at(day(2024, 10, 27), NaiveTime::from_hms_opt(2, 30, 0).unwrap()), utc("2024-10-27T00:30:00Z"));
+    }
+
+    #[test]
+    fn days_starting_in_a_dst_gap_start_after_it() {
+        // Brazil moved the clocks from midnight to 01:00
+        let sao_paulo = Zone::parse("America/Sao_Paulo").unwrap();
+        assert_eq!(sao_paulo.start_of(day(2018, 11, 4)), utc("2018-11-04T03:00:00Z"));
+        assert_eq!(sao_paulo.date_of(utc("2018-11-04T03:00:00Z")), day(2018, 11, 4));
+    }
+
+    #[test]
+    fn ranges_are_half_open() {
+        let range = Range::day(Zone::parse("UTC").unwrap(), day(2024, 5, 1));
+        assert!(range.contains(utc("2024-05-01T00:00:00Z")));
+        assert!(range.contains(utc("2024-05-01T23:59:59Z")));
+        assert!(!range.contains(utc("2024-05-02T00:00:00Z")));
+    }
+
+    #[test]
+    fn zone_names() {
+        assert_eq!(Zone::parse("local"), Ok(Zone::System));
+        assert!(Zone::parse("Mars/Olympus_Mons").is_err());
+    }
+}
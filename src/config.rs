@@ -1,6 +1,23 @@
+//! The user configuration, a `key=value` file at `~/.config/cupcli/cfg`.
+
+use std::path::PathBuf;
+
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use regex::Regex;
 use std::{env, fs};
 
-#[derive(Debug)]
+use crate::cache::CacheMode;
+use crate::calendar::DaysOff;
+use crate::clock::Zone;
+use crate::error::Error;
+use crate::pomodoro::Plan;
+use crate::project;
+
+// ClickUp ids after a `CU-` prefix, or custom task ids like `DEV-421`
+const DEFAULT_BRANCH_PATTERN: &str = r"CU-([0-9a-z]+)|([A-Z][A-Z0-9]+-[0-9]+)";
+
+/// Settings read from the cfg file plus runtime flags of the current invocation.
+#[derive(Debug, Clone)]
 pub struct Cfg {
     pub token: String,
     pub team_id: String,
@@ -9,11 +26,61 @@ pub struct Cfg {
     pub folder_id: String,
     pub list_id: String,
     pub daily_quota: f32,
+    // hours to work per weekday, Monday first; defaults to daily_quota from Monday to Friday
+    pub schedule: [f32; 7],
+    // the `holidays` key: an .ics file or a country code like de-by
+    pub holidays: Option<String>,
+    // public holidays and vacations, loaded from the files by build_global_cfg
+    pub days_off: DaysOff,
+    // first day counted by `balance`
+    pub balance_start: Option<NaiveDate>,
+    // timezone days are counted in, the system one unless `timezone` names an IANA zone
+    pub zone: Zone,
+    // `time check` looks for gaps within the working hours and flags entries longer than long_entry_hours
+    pub work_start: NaiveTime,
+    pub work_end: NaiveTime,
+    pub gap_threshold_mins: i64,
+    pub long_entry_hours: i64,
     pub look_behind: u64,
+    // finds the task id in git branch names like `feature/CU-86abc123-login-fix` or `DEV-421-something`
+    pub branch_pattern: Regex,
+    // remind to track after this many minutes without a timer within the working hours, 0 disables it in the daemon
+    pub remind_after_mins: i64,
+    // run instead of the desktop notification, gets $CUPCLI_SUMMARY and $CUPCLI_BODY
+    pub remind_hook: Option<String>,
+    // block lengths of `timer pomodoro` and the hook run at its transitions instead of the desktop notification
+    pub pomodoro: Plan,
+    pub pomodoro_hook: Option<String>,
+    // applied to new time entries and timers unless tags or billable are given on the command line
+    pub default_tags: Vec<String>,
+    pub default_billable: Option<bool>,
+    // cache TTLs in minutes per cached kind
+    pub ttl_tasks: u64,
+    pub ttl_statuses: u64,
+    pub ttl_members: u64,
+    pub ttl_hierarchy: u64,
+    pub ttl_entries: u64,
+    pub ttl_timer: u64,
+    // set from the --no-cache / --refresh flags, not from the cfg file
+    pub cache_mode: CacheMode,
+    // request layer: timeouts in seconds, retries after the first attempt and the first backoff delay
+    pub timeout_secs: u64,
+    pub connect_timeout_secs: u64,
+    pub max_retries: u32,
+    pub retry_base_ms: u64,
+    // how many requests a single command may have in flight at once
+    pub max_concurrency: usize,
+    // seconds between two polls of the current timer by `cupcli daemon`
+    pub daemon_poll_secs: u64,
+    // set from the --debug flag, logs every request and the remaining rate limit budget
+    pub debug: bool,
+    // settings that were ignored and why, for the caller to show
+    pub warnings: Vec<String>,
 }
 
 impl FromIterator<(String, String)> for Cfg {
     fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        let mut schedule = None;
         let mut cfg = Cfg {
             token: String::new(),
             team_id: String::new(),
@@ -22,7 +89,38 @@ impl FromIterator<(String, String)> for Cfg {
             folder_id: String::new(),
             list_id: String::new(),
             daily_quota: 8.0,
+            schedule: [0.0; 7],
+            holidays: None,
+            days_off: DaysOff::default(),
+            balance_start: None,
+            zone: Zone::System,
+            work_start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            work_end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            gap_threshold_mins: 15,
+            long_entry_hours: 10,
             look_behind: 1,
+            branch_pattern: Regex::new(DEFAULT_BRANCH_PATTERN).unwrap(),
+            remind_after_mins: 30,
+            remind_hook: None,
+            pomodoro: Plan::default(),
+            pomodoro_hook: None,
+            default_tags: Vec::new(),
+            default_billable: None,
+            ttl_tasks: 15,
+            ttl_statuses: 24 * 60,
+            ttl_members: 24 * 60,
+            ttl_hierarchy: 24 * 60,
+            ttl_entries: 5,
+            ttl_timer: 1,
+            cache_mode: CacheMode::Normal,
+            timeout_secs: 30,
+            connect_timeout_secs: 10,
+            max_retries: 3,
+            retry_base_ms: 500,
+            max_concurrency: 4,
+            daemon_poll_secs: 30,
+            debug: false,
+            warnings: Vec::new(),
         };
         for (key, value) in iter {
             match key.as_str() {
@@ -33,44 +131,229 @@ impl FromIterator<(String, String)> for Cfg {
                 "folderid" => cfg.folder_id = value,
                 "listid" => cfg.list_id = value,
                 "daily_quota" => cfg.daily_quota = value.parse::<f32>().unwrap_or(8.0),
+                "schedule" => match parse_schedule(&value) {
+                    Ok(parsed) => schedule = Some(parsed),
+                    Err(e) => cfg.warnings.push(format!("Ignoring invalid schedule `{}`: {}", value, e)),
+                },
+                "holidays" => cfg.holidays = Some(value),
+                "working_hours" => match parse_working_hours(&value) {
+                    Some((start, end)) => (cfg.work_start, cfg.work_end) = (start, end),
+                    None => cfg.warnings.push(format!("Ignoring working_hours `{}`, expected e.g. 09:00-17:00", value)),
+                },
+                "gap_threshold" => cfg.gap_threshold_mins = value.parse::<i64>().unwrap_or(15),
+                "long_entry" => cfg.long_entry_hours = value.parse::<i64>().unwrap_or(10),
+                "timezone" => match Zone::parse(&value) {
+                    Ok(zone) => cfg.zone = zone,
+                    Err(e) => cfg.warnings.push(format!("Ignoring timezone `{}`: {}", value, e)),
+                },
+                "balance_start" => cfg.balance_start = value.trim().parse::<NaiveDate>().ok(),
+                "remind_after" => cfg.remind_after_mins = value.parse::<i64>().unwrap_or(30),
+                "remind_hook" => cfg.remind_hook = Some(value).filter(|hook| !hook.trim().is_empty()),
+                "pomodoro" => match Plan::parse(&value) {
+                    Ok(plan) => cfg.pomodoro = plan,
+                    Err(e) => cfg.warnings.push(format!("Ignoring pomodoro `{}`: {}", value, e)),
+                },
+                "pomodoro_hook" => cfg.pomodoro_hook = Some(value).filter(|hook| !hook.trim().is_empty()),
+                "branch_pattern" => match Regex::new(&value) {
+                    Ok(pattern) => cfg.branch_pattern = pattern,
+                    Err(e) => cfg.warnings.push(format!("Ignoring branch_pattern `{}`: {}", value, e)),
+                },
                 "look_behind" => cfg.look_behind = value.parse::<u64>().unwrap_or(1),
-                _ => println!("[WARNING] Ignoring unknown key in cfg `{}`", key)
+                "default_tags" => cfg.default_tags = value.split(',').map(str::trim).filter(|t| !t.is_empty()).map(str::to_string).collect(),
+                "default_billable" => cfg.default_billable = value.parse::<bool>().ok(),
+                "ttl_tasks" => cfg.ttl_tasks = value.parse::<u64>().unwrap_or(15),
+                "ttl_statuses" => cfg.ttl_statuses = value.parse::<u64>().unwrap_or(24 * 60),
+                "ttl_members" => cfg.ttl_members = value.parse::<u64>().unwrap_or(24 * 60),
+                "ttl_hierarchy" => cfg.ttl_hierarchy = value.parse::<u64>().unwrap_or(24 * 60),
+                "ttl_entries" => cfg.ttl_entries = value.parse::<u64>().unwrap_or(5),
+                "ttl_timer" => cfg.ttl_timer = value.parse::<u64>().unwrap_or(1),
+                "timeout" => cfg.timeout_secs = value.parse::<u64>().unwrap_or(30),
+                "connect_timeout" => cfg.connect_timeout_secs = value.parse::<u64>().unwrap_or(10),
+                "max_retries" => cfg.max_retries = value.parse::<u32>().unwrap_or(3),
+                "retry_base_ms" => cfg.retry_base_ms = value.parse::<u64>().unwrap_or(500),
+                "max_concurrency" => cfg.max_concurrency = value.parse::<usize>().unwrap_or(4).max(1),
+                "daemon_poll" => cfg.daemon_poll_secs = value.parse::<u64>().unwrap_or(30).max(1),
+                _ => cfg.warnings.push(format!("Ignoring unknown key in cfg `{}`", key)),
             }
         }
+        cfg.schedule = schedule.unwrap_or([cfg.daily_quota, cfg.daily_quota, cfg.daily_quota, cfg.daily_quota, cfg.daily_quota, 0.0, 0.0]);
         cfg
     }
 }
 
-fn parse_cfg() -> Vec<(String, String)> {
+impl Cfg {
+    /// Hours to work on `day` according to the schedule, none on holidays and vacations.
+    pub fn quota(&self, day: NaiveDate) -> f32 {
+        if self.days_off.is_off(day) {
+            return 0.0;
+        }
+        self.schedule[day.weekday().num_days_from_monday() as usize]
+    }
+
+    /// Hours to work from `first` to `last`, both inclusive.
+    pub fn quota_between(&self, first: NaiveDate, last: NaiveDate) -> f32 {
+        first.iter_days().take_while(|day| *day <= last).map(|day| self.quota(day)).sum()
+    }
+}
+
+// parses `09:00-17:00`
+fn parse_working_hours(value: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let (start, end) = value.split_once('-')?;
+    let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?;
+    let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?;
+    Some((start, end)).filter(|(start, end)| start < end)
+}
+
+fn parse_weekday(name: &str) -> Result<Weekday, String> {
+    name.trim().parse::<Weekday>().map_err(|_| format!("unknown weekday `{}`", name.trim()))
+}
+
+// parses `mon-thu:8,fri:4` into hours per weekday, days that are not mentioned are off
+fn parse_schedule(value: &str) -> Result<[f32; 7], String> {
+    let mut schedule = [0.0; 7];
+    for part in value.split(',').filter(|p| !p.trim().is_empty()) {
+        let (days, hours) = part.split_once(':').ok_or(format!("expected `day:hours`, got `{}`", part.trim()))?;
+        let hours = hours.trim().parse::<f32>().map_err(|_| format!("invalid hours `{}`", hours.trim()))?;
+        let (first, last) = match days.split_once('-') {
+            Some((first, last)) => (parse_weekday(first)?, parse_weekday(last)?),
+            None => (parse_weekday(days)?, parse_weekday(days)?),
+        };
+        let mut day = first;
+        loop {
+            schedule[day.num_days_from_monday() as usize] = hours;
+            if day == last {
+                break;
+            }
+            day = day.succ();
+        }
+    }
+    Ok(schedule)
+}
+
+/// Local state that must survive `cache clear`, e.g. the offline journal: `$XDG_DATA_HOME/cupcli`.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = match env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".local").join("share"),
+    };
+    Some(base.join("cupcli"))
+}
+
+/// The cfg file, `~/.config/cupcli/cfg`.
+pub fn cfg_path() -> PathBuf {
     let home = env::var("HOME").expect("Could not get $HOME env var; expose it first!");
-    let cfg_str = fs::read_to_string(format!("{}/.config/cupcli/cfg", home)).expect(
-        r#"
-        ---------------------------------------------------------------------------
-        Config file not found!
-        Please create ~/.config/cupcli/cfg and add the following:
-        cu_auth={YOUR_CLICKUP_AUTH_TOKEN}
-        teamid={YOUR_TEAM_ID}
-        ---------------------------------------------------------------------------
-        "#,
-    );
-    let cfg: Vec<(String, String)> = cfg_str
-        .lines()
-        .map(|line| {
-            let mut split = line.split("=");
-            let key = split.next().unwrap().to_string();
-            let value = split.next().unwrap().to_string();
-            (key, value)
+    PathBuf::from(home).join(".config").join("cupcli").join("cfg")
+}
+
+// `key=value` lines, values may contain `=` themselves; blank lines and `#` comments are skipped
+fn parse_lines(raw: &str, warnings: &mut Vec<String>) -> Vec<(String, String)> {
+    raw.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| match line.split_once('=') {
+            Some((key, value)) => Some((key.trim().to_string(), value.trim().to_string())),
+            None => {
+                warnings.push(format!("Ignoring cfg line `{}`, expected key=value", line));
+                None
+            }
         })
-        .collect();
+        .collect()
+}
 
-    cfg
+/// Reads and validates the cfg file, fails with setup instructions if it is missing. Inside a
+/// project the settings of its `.cupcli.toml` replace the global ones, see [`crate::project`].
+/// Ignored settings end up in [`Cfg::warnings`].
+pub fn build_cfg() -> Result<Cfg, Error> {
+    let mut cfg = build_global_cfg()?;
+    match project::load() {
+        Ok(Some((path, project))) => {
+            for e in project.apply(&mut cfg) {
+                cfg.warnings.push(format!("Ignoring {}: {}", path.display(), e));
+            }
+        }
+        Ok(None) => {}
+        Err(e) => cfg.warnings.push(format!("Ignoring {}", e)),
+    }
+    Ok(cfg)
 }
 
-pub fn build_cfg() -> Cfg {
-    let cfg = parse_cfg();
-    let cfg: Cfg = cfg.into_iter().collect();
+/// Like [`build_cfg`] without the settings of the working directory's project, for long-running
+/// processes serving several projects.
+pub fn build_global_cfg() -> Result<Cfg, Error> {
+    let path = cfg_path();
+    let raw = fs::read_to_string(&path).map_err(|e| {
+        Error::Config(format!(
+            "Could not read the config file {}: {}\nPlease create it and add the following:\ncu_auth={{YOUR_CLICKUP_AUTH_TOKEN}}\nteamid={{YOUR_TEAM_ID}}",
+            path.display(),
+            e
+        ))
+    })?;
+    let mut warnings = Vec::new();
+    let mut cfg: Cfg = parse_lines(&raw, &mut warnings).into_iter().collect();
     if cfg.token.is_empty() || cfg.team_id.is_empty() {
-        panic!("cu_auth and teamid must be set in the config file!");
+        return Err(Error::Config(format!("cu_auth and teamid must be set in {}", path.display())));
+    }
+    warnings.append(&mut cfg.warnings);
+    cfg.warnings = warnings;
+    match DaysOff::load(cfg.holidays.as_deref()) {
+        Ok(days_off) => cfg.days_off = days_off,
+        Err(e) => {
+            cfg.warnings.push(format!("Ignoring holidays: {}", e));
+            cfg.days_off = DaysOff::load(None).unwrap();
+        }
+    }
+    Ok(cfg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Days;
+
+    fn cfg(pairs: &[(&str, &str)]) -> Cfg {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn cfg_lines_split_at_the_first_equals_sign() {
+        let raw = "# ClickUp\ncu_auth = pk_1=2\n\n  teamid=42  \nnot a setting\n";
+        let mut warnings = Vec::new();
+        let pairs = parse_lines(raw, &mut warnings);
+        assert_eq!(pairs, [("cu_auth".to_string(), "pk_1=2".to_string()), ("teamid".to_string(), "42".to_string())]);
+        assert_eq!(warnings, ["Ignoring cfg line `not a setting`, expected key=value"]);
+    }
+
+    #[test]
+    fn schedule_ranges_and_single_days() {
+        assert_eq!(parse_schedule("mon-thu:8,fri:4").unwrap(), [8.0, 8.0, 8.0, 8.0, 4.0, 0.0, 0.0]);
+        assert_eq!(parse_schedule("tue:6.5, sat-sun:2").unwrap(), [0.0, 6.5, 0.0, 0.0, 0.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn schedule_wraps_around_the_week() {
+        assert_eq!(parse_schedule("fri-mon:5").unwrap(), [5.0, 0.0, 0.0, 0.0, 5.0, 5.0, 5.0]);
+    }
+
+    #[test]
+    fn schedule_rejects_garbage() {
+        assert!(parse_schedule("mon 8").is_err());
+        assert!(parse_schedule("mon:eight").is_err());
+        assert!(parse_schedule("funday:8").is_err());
+    }
+
+    #[test]
+    fn quota_follows_the_schedule() {
+        let cfg = cfg(&[("schedule", "mon-thu:8,fri:4")]);
+        // 2024-06-07 is a Friday
+        let friday = NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+        assert_eq!(cfg.quota(friday), 4.0);
+        assert_eq!(cfg.quota(friday.succ_opt().unwrap()), 0.0);
+        assert_eq!(cfg.quota_between(friday - Days::new(4), friday + Days::new(2)), 36.0);
     }
-    cfg
-}
\ No newline at end of file
+
+    #[test]
+    fn daily_quota_without_schedule_covers_weekdays() {
+        let cfg = cfg(&[("daily_quota", "7.5")]);
+        assert_eq!(cfg.schedule, [7.5, 7.5, 7.5, 7.5, 7.5, 0.0, 0.0]);
+    }
+}
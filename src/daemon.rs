@@ -0,0 +1,288 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+use std::{env, fs, panic, process, thread};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::args::ArgError;
+use crate::display::{ERROR, HOURGLASS};
+use crate::{execute, Reply};
+use cupcli::cache::{self, CacheMode};
+use cupcli::config::{build_global_cfg, cfg_path, data_dir};
+use cupcli::git;
+use cupcli::project;
+use cupcli::remind;
+use cupcli::{Cfg, Error};
+
+// one JSON line per request and per reply
+#[derive(Serialize, Deserialize)]
+enum Request {
+    Run(Vec<String>),
+    Status,
+    Stop,
+}
+
+struct State {
+    cfg: Cfg,
+    // of the cfg file and the files of its days off, in this order
+    cfg_modified: Vec<Option<SystemTime>>,
+    started: DateTime<Local>,
+    served: u64,
+}
+
+// $XDG_RUNTIME_DIR is private to the user; not the cache dir, `cache clear` would remove the socket
+fn socket_path() -> Option<PathBuf> {
+    match env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) if !dir.is_empty() => Some(PathBuf::from(dir).join("cupcli.sock")),
+        _ => Some(data_dir()?.join("daemon.sock")),
+    }
+}
+
+// commands that neither prompt nor print while running, everything else runs in the calling process
+fn forwardable(args: &[String]) -> bool {
+    // request logs of --debug would end up on the daemon's stderr
+    if args.iter().any(|arg| arg == "--debug") {
+        return false;
+    }
+    // the picker needs the calling terminal
+    if crate::wants_picker(args).is_some() {
+        return false;
+    }
+    let mut args = args.iter().map(String::as_str).filter(|arg| !matches!(*arg, "--no-cache" | "--refresh"));
+    match args.next() {
+        Some("timeget" | "taskget" | "statusbar" | "tag" | "balance" | "vacation" | "cache" | "complete" | "sync") => true,
+        // the task may come from the git branch of the calling process' working directory
+        Some("timetrack") => git::current_branch().is_none(),
+        Some("timer") => match args.next() {
+            Some("start") => git::current_branch().is_none(),
+            sub => matches!(sub, Some("stop" | "current")),
+        },
+        Some("task") => matches!(args.next(), Some("status" | "list" | "comments" | "comment")),
+        _ => false,
+    }
+}
+
+fn connect() -> Option<UnixStream> {
+    UnixStream::connect(socket_path()?).ok()
+}
+
+fn exchange(stream: UnixStream, request: &Request) -> Reply {
+    let exchanged = (|| {
+        let raw = serde_json::to_string(request).map_err(|e| e.to_string())?;
+        writeln!(&stream, "{}", raw).map_err(|e| e.to_string())?;
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).map_err(|e| e.to_string())?;
+        serde_json::from_str::<Reply>(&line).map_err(|e| e.to_string())
+    })();
+    exchanged.unwrap_or_else(|e| Err(format!("{} [DAEMON ERROR] {}", ERROR, e)))
+}
+
+/// Runs the command in the daemon if one is running and the command can run there, None otherwise.
+pub fn forward(args: &[String]) -> Option<Reply> {
+    // the daemon only knows the global cfg, not the `.cupcli.toml` of the calling process
+    if !forwardable(args) || project::find().is_some() {
+        return None;
+    }
+    Some(exchange(connect()?, &Request::Run(args.to_vec())))
+}
+
+/// `cupcli daemon [start|status|stop]`
+pub fn command(args: &[String]) -> Reply {
+    let request = match args.first().map(String::as_str) {
+        None | Some("start") => return serve(),
+        Some("status") => Request::Status,
+        Some("stop") => Request::Stop,
+        Some(_) => return Err(ArgError::ArgValue("Invalid second argument for first argument 'daemon'. Only 'start', 'status' and 'stop' are valid!".to_string()).to_string()),
+    };
+    match connect() {
+        Some(stream) => exchange(stream, &request),
+        None => Ok("No daemon running".to_string()),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).ok()?.modified().ok()
+}
+
+fn cfg_modified(cfg: &Cfg) -> Vec<Option<SystemTime>> {
+    let sources = cfg.days_off.sources();
+    [cfg_path()].iter().chain(&sources).map(|path| modified(path)).collect()
+}
+
+// a changed cfg file, vacation added by a forwarded command or edited .ics file is picked up by
+// the next command
+fn current_cfg(state: &Mutex<State>) -> Cfg {
+    let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+    let modified = cfg_modified(&state.cfg);
+    if modified[0].is_some() && modified != state.cfg_modified {
+        match build_global_cfg() {
+            Ok(cfg) => {
+                crate::warn(&cfg);
+                state.cfg = cfg;
+                state.cfg_modified = cfg_modified(&state.cfg);
+            }
+            // e.g. saved halfway, the commands keep the previous cfg until it is valid again
+            Err(e) => eprintln!("{} {}", ERROR, e),
+        }
+    }
+    state.cfg.clone()
+}
+
+// the daemon's working directory is not the one of the calling processes, a git branch found
+// there must not name the task of a forwarded `timetrack` or `timer start` without a task id
+fn detach() -> io::Result<()> {
+    env::set_current_dir("/")
+}
+
+fn serve() -> Reply {
+    detach().map_err(|e| format!("{} Could not leave the working directory: {}", ERROR, e))?;
+    let path = socket_path().ok_or(format!("{} Could not find a directory for the daemon socket", ERROR))?;
+    if UnixStream::connect(&path).is_ok() {
+        return Err(format!("{} A daemon is already listening on {}", ERROR, path.display()));
+    }
+    // left behind by a daemon that didn't shut down cleanly
+    let _ = fs::remove_file(&path);
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let listener = UnixListener::bind(&path).map_err(|e| format!("{} Could not listen on {}: {}", ERROR, path.display(), e))?;
+    // the daemon acts with the user's token, nobody else may talk to it
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).map_err(|e| format!("{} Could not restrict {}: {}", ERROR, path.display(), e))?;
+    let cfg = build_global_cfg().map_err(|e| format!("{} {}", ERROR, e))?;
+    crate::warn(&cfg);
+    let state = Arc::new(Mutex::new(State {
+        cfg_modified: cfg_modified(&cfg),
+        cfg,
+        started: Local::now(),
+        served: 0,
+    }));
+    {
+        let state = Arc::clone(&state);
+        thread::spawn(move || poll(&state));
+    }
+    println!("{} Listening on {}", HOURGLASS, path.display());
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let state = Arc::clone(&state);
+        let path = path.clone();
+        thread::spawn(move || handle(stream, &state, &path));
+    }
+    Ok(String::new())
+}
+
+fn respond(stream: &UnixStream, reply: &Reply) {
+    if let Ok(raw) = serde_json::to_string(reply) {
+        let _ = writeln!(&*stream, "{}", raw);
+    }
+}
+
+fn handle(stream: UnixStream, state: &Mutex<State>, path: &Path) {
+    let mut line = String::new();
+    if BufReader::new(&stream).read_line(&mut line).is_err() {
+        return;
+    }
+    let reply = match serde_json::from_str::<Request>(&line) {
+        Ok(Request::Run(args)) => {
+            let cfg = current_cfg(state);
+            state.lock().unwrap_or_else(PoisonError::into_inner).served += 1;
+            // a panicking command must still answer, the client would only see the dropped connection
+            panic::catch_unwind(|| execute(cfg, args)).unwrap_or_else(|payload| {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|m| m.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                Err(format!("{} [DAEMON ERROR] The command panicked: {}", ERROR, message))
+            })
+        }
+        Ok(Request::Status) => {
+            let state = state.lock().unwrap_or_else(PoisonError::into_inner);
+            Ok(format!(
+                "{} Daemon {} running since {}, {} commands served",
+                HOURGLASS,
+                process::id(),
+                state.started.format("%d/%m/%Y %H:%M"),
+                state.served
+            ))
+        }
+        Ok(Request::Stop) => {
+            respond(&stream, &Ok("Daemon stopped".to_string()));
+            let _ = fs::remove_file(path);
+            process::exit(0);
+        }
+        Err(e) => Err(format!("{} [DAEMON ERROR] Invalid request: {}", ERROR, e)),
+    };
+    respond(&stream, &reply);
+}
+
+// the timer is fetched on every poll, today's entries when the timer changed or their TTL ran out
+// and the tasks when their TTL ran out, so forwarded commands and the statusbar find them cached
+fn refresh(cfg: &Cfg, last_timer: &mut Option<String>) -> Result<(), Error> {
+    let refreshing = Cfg {
+        cache_mode: CacheMode::Refresh,
+        ..cfg.clone()
+    };
+    let timer = cache::current_timer(&refreshing)?.map(|timer| timer.id);
+    let today = cfg.zone.today();
+    if timer != *last_timer {
+        cache::day_entries(&refreshing, today)?;
+        *last_timer = timer;
+    } else {
+        cache::day_entries(cfg, today)?;
+    }
+    cache::team_tasks(cfg)?;
+    Ok(())
+}
+
+// reminds again after another `remind_after` minutes at the earliest
+fn remind_due(cfg: &Cfg, last_reminder: Option<DateTime<Local>>) -> bool {
+    cfg.remind_after_mins > 0 && last_reminder.is_none_or(|last| Local::now() - last >= chrono::Duration::minutes(cfg.remind_after_mins))
+}
+
+fn poll(state: &Mutex<State>) {
+    let mut last_timer = None;
+    let mut last_reminder = None;
+    loop {
+        let cfg = current_cfg(state);
+        match refresh(&cfg, &mut last_timer) {
+            Err(e) => eprintln!("[WARNING] Polling ClickUp failed: {}", e),
+            // both were just refreshed, the cache serves them
+            Ok(()) if remind_due(&cfg, last_reminder) => {
+                let reminded = cache::current_timer(&cfg)
+                    .and_then(|timer| Ok((timer, cache::day_entries(&cfg, cfg.zone.today())?)))
+                    .map_err(|e| e.to_string())
+                    .and_then(|(timer, entries)| remind::remind(&cfg, timer.as_ref(), &entries));
+                match reminded {
+                    Ok(Some(_)) => last_reminder = Some(Local::now()),
+                    Ok(None) => {}
+                    Err(e) => eprintln!("[WARNING] Could not send the reminder: {}", e),
+                }
+            }
+            Ok(()) => {}
+        }
+        thread::sleep(Duration::from_secs(cfg.daemon_poll_secs));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwarded_calls_see_no_branch() {
+        let repo = env::temp_dir().join(format!("cupcli-daemon-{}", process::id()));
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::write(repo.join(".git").join("HEAD"), "ref: refs/heads/CU-86abc123-login\n").unwrap();
+        env::set_current_dir(&repo).unwrap();
+        let cfg: Cfg = Vec::<(String, String)>::new().into_iter().collect();
+        assert_eq!(git::branch_task_id(&cfg).as_deref(), Ok("86abc123"));
+        detach().unwrap();
+        assert!(git::branch_task_id(&cfg).is_err());
+        fs::remove_dir_all(&repo).unwrap();
+    }
+}
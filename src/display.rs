@@ -0,0 +1,58 @@
+use chrono::Utc;
+
+use cupcli::clock::Zone;
+use cupcli::models::{hours, Comment, Task, TimeEntry};
+
+pub const HOURGLASS: char = '\u{231B}';
+pub const ERROR: char = '\u{1F6AB}';
+const ALARM_CLOCK: char = '\u{23F0}';
+const CHECKMARK: char = '\u{2705}';
+const LABEL: char = '\u{1F4CA}';
+pub const TAG: char = '\u{1F3F7}';
+pub const PALM_TREE: char = '\u{1F334}';
+pub const STOPWATCH: char = '\u{23F1}';
+pub const TOMATO: char = '\u{1F345}';
+
+pub fn fmt_time(hours: f32) -> String {
+    // an empty sum is -0.0, which would print as -0h
+    let hours = hours + 0.0;
+    if hours.fract() == 0.0 {
+        format!("{:.0}h", hours)
+    } else {
+        format!("{:.2}h", hours)
+    }
+}
+/// Hours with an explicit sign, for over- and undertime.
+pub fn fmt_balance(hours: f32) -> String {
+    let sign = if hours < 0.0 { "-" } else { "+" };
+    format!("{}{}", sign, fmt_time(hours.abs()))
+}
+pub fn fmt_task_line(task: &Task) -> String {
+    format!("{} {} [{}] ({})", CHECKMARK, task.name, task.status.status, task.id)
+}
+
+pub fn fmt_comment(comment: &Comment, zone: Zone) -> String {
+    let date = zone.naive(comment.date);
+    let author = comment.user.username.as_deref().unwrap_or("unknown");
+    format!("{} [{}] {}: {}", LABEL, date.format("%d/%m/%Y %H:%M"), author, comment.comment_text.trim())
+}
+
+pub fn fmt_task(entry: &TimeEntry) -> String {
+    let mut out = String::with_capacity(64);
+    let last_tracked_in_mins = Utc::now().signed_duration_since(entry.end_or_now()).num_minutes();
+
+    if let Some(task) = &entry.task {
+        out.push_str(&format!("{: <14}", &format!("{} [TASK]", CHECKMARK)));
+        out.push_str(&format!(" {} ({})\n", task.name, entry.task_url.as_deref().unwrap_or_default()));
+        out.push_str(&format!("{: <14}", &format!("{} [STATUS]", LABEL)));
+        out.push_str(&format!(" {}\n", task.status.status));
+    } else {
+        out.push_str("No task associated with this entry\n");
+    }
+
+    out.push_str(&format!("{: <14}", &format!("{} [LAST ENTRY]", ALARM_CLOCK)));
+    out.push_str(&format!(" {} minutes ({}) ago\n", last_tracked_in_mins, fmt_time(last_tracked_in_mins as f32 / 60f32)));
+    out.push_str(&format!("{: <14}", &format!("{} [DURATION]", HOURGLASS)));
+    out.push_str(&format!(" {} minutes ({})\n", entry.elapsed().num_minutes(), fmt_time(hours(entry.elapsed()))));
+    out
+}
@@ -0,0 +1,58 @@
+//! The error of the library: a failed ClickUp request or an unusable cfg.
+
+use std::fmt;
+
+use reqwest::StatusCode;
+
+/// Why a request to ClickUp failed or the cfg could not be built.
+#[derive(Debug)]
+pub enum Error {
+    /// the request could not be sent or ClickUp answered with an error status
+    Http(reqwest::Error),
+    /// the body could not be encoded or the response didn't match the [`crate::models`]
+    Decode(serde_json::Error),
+    /// the cfg file is missing or lacks required settings
+    Config(String),
+}
+
+impl Error {
+    /// The HTTP status ClickUp answered with, None if the request never got an answer.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Http(e) => e.status(),
+            Error::Decode(_) | Error::Config(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "{}", e),
+            Error::Decode(e) => write!(f, "unexpected response from ClickUp: {}", e),
+            Error::Config(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(e) => Some(e),
+            Error::Decode(e) => Some(e),
+            Error::Config(_) => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Decode(e)
+    }
+}
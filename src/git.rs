@@ -0,0 +1,191 @@
+//! The task id in the name of the checked out git branch, read from `.git/HEAD` without a git binary.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Cfg;
+
+// `.git` is a directory in a plain clone and a `gitdir: <path>` file in worktrees and submodules
+fn git_dir(dir: &Path) -> Option<PathBuf> {
+    let dot_git = dir.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let raw = fs::read_to_string(&dot_git).ok()?;
+    let target = raw.trim().strip_prefix("gitdir:")?.trim();
+    Some(dir.join(target))
+}
+
+/// The git directory of the repository containing the working directory.
+pub fn repo_git_dir() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors().find_map(git_dir)
+}
+
+// the top of the working tree containing the working directory
+fn work_tree() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors().find(|dir| git_dir(dir).is_some()).map(Path::to_path_buf)
+}
+
+// worktrees share the config and hooks of the main repository
+fn common_dir() -> Option<PathBuf> {
+    let git_dir = repo_git_dir()?;
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(raw) => Some(git_dir.join(raw.trim())),
+        Err(_) => Some(git_dir),
+    }
+}
+
+// the last value of `section.key` in a git config file; subsections and includes are ignored
+fn config_value(raw: &str, section: &str, key: &str) -> Option<String> {
+    let mut current = String::new();
+    let mut value = None;
+    for line in raw.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.split(']').next()) {
+            current = header.trim().to_lowercase();
+        } else if let Some((k, v)) = line.split_once('=') {
+            if current == section && k.trim().eq_ignore_ascii_case(key) {
+                value = Some(v.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    value
+}
+
+/// `core.hooksPath` of the repository or the user's git config, which makes git run the hooks
+/// from there instead of `.git/hooks`.
+pub fn hooks_path() -> Option<PathBuf> {
+    let home = env::var("HOME").ok().map(PathBuf::from);
+    let xdg = env::var("XDG_CONFIG_HOME").ok().map(PathBuf::from).or(home.as_ref().map(|home| home.join(".config")));
+    // in git's order of precedence, the repository's config wins
+    let configs = [common_dir().map(|dir| dir.join("config")), home.as_ref().map(|home| home.join(".gitconfig")), xdg.map(|dir| dir.join("git").join("config"))];
+    let value = configs.into_iter().flatten().find_map(|path| config_value(&fs::read_to_string(path).ok()?, "core", "hooksPath"))?;
+    let path = match (value.strip_prefix("~/"), &home) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(value),
+    };
+    // relative paths are taken from the top of the working tree, where git runs the hooks
+    Some(work_tree().map_or(path.clone(), |top| top.join(&path)))
+}
+
+/// Where git runs the hooks of the repository from.
+pub fn hooks_dir() -> Option<PathBuf> {
+    let common = common_dir()?;
+    Some(hooks_path().unwrap_or(common.join("hooks")))
+}
+
+/// Whether a rebase is replaying commits, their time was booked when they were first made.
+pub fn rebasing() -> bool {
+    repo_git_dir().is_some_and(|dir| dir.join("rebase-merge").exists() || dir.join("rebase-apply").exists())
+}
+
+/// The checked out branch of the repository containing the working directory, None outside a
+/// repository or with a detached HEAD.
+pub fn current_branch() -> Option<String> {
+    let head = fs::read_to_string(repo_git_dir()?.join("HEAD")).ok()?;
+    head.trim().strip_prefix("ref: refs/heads/").map(str::to_string)
+}
+
+/// The task id in `branch` according to `branch_pattern`: the first capture group that matched,
+/// the whole match if the pattern has none.
+pub fn task_id(cfg: &Cfg, branch: &str) -> Option<String> {
+    let captures = cfg.branch_pattern.captures(branch)?;
+    let id = captures.iter().skip(1).flatten().next().or(captures.get(0))?;
+    Some(id.as_str().to_string())
+}
+
+/// The task id of the checked out branch, or why there is none.
+pub fn branch_task_id(cfg: &Cfg) -> Result<String, String> {
+    let branch = current_branch().ok_or("Not on a branch of a git repository")?;
+    task_id(cfg, &branch).ok_or(format!("The branch `{}` doesn't match the branch_pattern `{}`", branch, cfg.branch_pattern))
+}
+
+/// Custom task ids like `DEV-421` carry a prefix, ClickUp's own ids like `86abc123` don't.
+pub fn is_custom_id(id: &str) -> bool {
+    id.contains('-')
+}
+
+/// How a commit message refers to a task: ClickUp ids with a `CU-` prefix, custom ids as they are.
+pub fn task_ref(id: &str) -> String {
+    if is_custom_id(id) {
+        id.to_string()
+    } else {
+        format!("CU-{}", id)
+    }
+}
+
+/// `message` with `reference` appended as its last paragraph, before git's comment lines;
+/// None if it mentions the reference already.
+pub fn link_message(message: &str, reference: &str) -> Option<String> {
+    if message.contains(reference) {
+        return None;
+    }
+    let first_comment = if message.starts_with('#') { Some(0) } else { message.find("\n#").map(|i| i + 1) };
+    let (body, comments) = match first_comment {
+        Some(i) => message.split_at(i),
+        None => (message, ""),
+    };
+    // an empty message keeps its first line free for the summary still to be written
+    Some(format!("{}\n\n{}\n{}", body.trim_end(), reference, comments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg(pattern: Option<&str>) -> Cfg {
+        let pairs = pattern.map(|p| ("branch_pattern".to_string(), p.to_string()));
+        pairs.into_iter().collect()
+    }
+
+    #[test]
+    fn default_pattern_finds_clickup_and_custom_ids() {
+        let cfg = cfg(None);
+        assert_eq!(task_id(&cfg, "feature/CU-86abc123-login").as_deref(), Some("86abc123"));
+        assert_eq!(task_id(&cfg, "DEV-421-fix-export").as_deref(), Some("DEV-421"));
+        assert_eq!(task_id(&cfg, "main"), None);
+    }
+
+    #[test]
+    fn patterns_without_groups_take_the_whole_match() {
+        let cfg = cfg(Some("PROJ-[0-9]+"));
+        assert_eq!(task_id(&cfg, "bugfix/PROJ-77").as_deref(), Some("PROJ-77"));
+        assert_eq!(task_id(&cfg, "bugfix/CU-86abc123"), None);
+    }
+
+    #[test]
+    fn links_go_before_the_comment_lines() {
+        assert_eq!(link_message("Fix login\n", "CU-1").as_deref(), Some("Fix login\n\nCU-1\n"));
+        let template = "Fix login\n\nBody\n# Please enter the commit message\n# On branch x\n";
+        assert_eq!(link_message(template, "CU-1").as_deref(), Some("Fix login\n\nBody\n\nCU-1\n# Please enter the commit message\n# On branch x\n"));
+    }
+
+    #[test]
+    fn empty_messages_keep_the_summary_line_free() {
+        let linked = link_message("\n# Please enter the commit message\n", "DEV-4").unwrap();
+        assert_eq!(linked, "\n\nDEV-4\n# Please enter the commit message\n");
+        assert!(linked.lines().next().unwrap().is_empty());
+    }
+
+    #[test]
+    fn linked_messages_stay_as_they_are() {
+        assert_eq!(link_message("Fix login\n\nCU-1\n", "CU-1"), None);
+    }
+
+    #[test]
+    fn hooks_path_from_git_config() {
+        let raw = "[core]\n\trepositoryformatversion = 0\n\thooksPath = .husky/_\n[remote \"origin\"]\n\thookspath = nope\n";
+        assert_eq!(config_value(raw, "core", "hooksPath").as_deref(), Some(".husky/_"));
+        assert_eq!(config_value("[Core]\nHOOKSPATH = \"/srv/hooks\"\n", "core", "hooksPath").as_deref(), Some("/srv/hooks"));
+        assert_eq!(config_value("[user]\nname = x\n", "core", "hooksPath"), None);
+    }
+
+    #[test]
+    fn references_by_kind_of_id() {
+        assert!(is_custom_id("DEV-421"));
+        assert_eq!(task_ref("86abc123"), "CU-86abc123");
+        assert_eq!(task_ref("DEV-421"), "DEV-421");
+    }
+}
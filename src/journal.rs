@@ -0,0 +1,351 @@
+//! Offline journal of time entries that could not be posted yet.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{data_dir, Cfg};
+use crate::client::Client;
+use crate::error::Error;
+use crate::models::{NewTimeEntry, TimeEntry};
+
+// two entries closer than this many seconds in start and duration are considered the same booking
+const DUPLICATE_TOLERANCE_SECS: i64 = 60;
+
+/// A time entry that could not be posted yet, replayed in order by [`sync`].
+#[derive(Debug, Deserialize, Serialize)]
+pub struct QueuedEntry {
+    pub queued_at: i64,
+    pub label: String,
+    pub body: NewTimeEntry,
+    #[serde(default)]
+    pub conflict: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct SyncOptions {
+    /// post entries that overlap existing ones instead of reporting them as conflicts
+    pub force: bool,
+    /// drop entries that are still conflicting after this sync
+    pub discard_conflicts: bool,
+    /// replay entries that conflicted before; automatic flushes leave them for an explicit sync
+    pub retry_conflicts: bool,
+}
+
+fn journal_path() -> Option<PathBuf> {
+    Some(data_dir()?.join("journal.jsonl"))
+}
+
+// held while the journal is read and rewritten, concurrent syncs of the daemon and the command
+// line would otherwise replay the same entry twice or drop one enqueued in between
+fn lock() -> std::io::Result<File> {
+    let dir = data_dir().ok_or(std::io::ErrorKind::NotFound)?;
+    fs::create_dir_all(&dir)?;
+    let file = OpenOptions::new().create(true).truncate(false).write(true).open(dir.join("journal.lock"))?;
+    // released when the file is closed, also if the process dies
+    file.lock()?;
+    Ok(file)
+}
+
+pub fn load() -> Vec<QueuedEntry> {
+    let Some(raw) = journal_path().and_then(|path| fs::read_to_string(path).ok()) else {
+        return Vec::new();
+    };
+    raw.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn save(entries: &[QueuedEntry]) -> std::io::Result<()> {
+    let path = journal_path().ok_or(std::io::ErrorKind::NotFound)?;
+    if entries.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    let mut raw = String::new();
+    for entry in entries {
+        raw.push_str(&serde_json::to_string(entry)?);
+        raw.push('\n');
+    }
+    fs::write(path, raw)
+}
+
+/// Appends a booking to the journal; the journal is append-only until the next sync rewrites it.
+pub fn enqueue(label: String, body: NewTimeEntry) -> std::io::Result<()> {
+    let path = journal_path().ok_or(std::io::ErrorKind::NotFound)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let _lock = lock()?;
+    let entry = QueuedEntry {
+        queued_at: Utc::now().timestamp_millis(),
+        label,
+        body,
+        conflict: None,
+    };
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)
+}
+
+pub fn is_empty() -> bool {
+    journal_path().is_none_or(|path| !path.exists())
+}
+
+fn same_task(queued: &QueuedEntry, entry: &TimeEntry) -> bool {
+    queued.body.tid.as_ref() == entry.task.as_ref().map(|t| &t.id)
+}
+
+enum Outcome {
+    Posted,
+    Duplicate,
+    Conflict(String),
+}
+
+// Duplicate if the entry was already booked, e.g. by a sync that died before rewriting the
+// journal, Conflict if it overlaps another entry; None if it can be posted
+fn judge(queued: &QueuedEntry, existing: &[TimeEntry], options: &SyncOptions) -> Option<Outcome> {
+    let tolerance = Duration::seconds(DUPLICATE_TOLERANCE_SECS);
+    let (start, end) = (queued.body.start, queued.body.end());
+    for entry in existing {
+        if same_task(queued, entry)
+            && (entry.start - start).abs() < tolerance
+            && (entry.duration - queued.body.duration).abs() < tolerance
+        {
+            return Some(Outcome::Duplicate);
+        }
+        if !options.force && entry.start < end && start < entry.end_or_now() {
+            let task = entry.task.as_ref().map_or("no task".to_string(), |t| t.name.clone());
+            return Some(Outcome::Conflict(format!("overlaps existing entry {} ({})", entry.id, task)));
+        }
+    }
+    None
+}
+
+fn replay(cfg: &Cfg, queued: &QueuedEntry, options: &SyncOptions) -> Result<Outcome, Error> {
+    let tolerance = Duration::seconds(DUPLICATE_TOLERANCE_SECS);
+    let client = Client::new(cfg);
+    let existing = client.time_entries(queued.body.start - tolerance, queued.body.end() + tolerance)?;
+    if let Some(outcome) = judge(queued, &existing, options) {
+        return Ok(outcome);
+    }
+    match client.create_time_entry(&queued.body) {
+        Ok(()) => Ok(Outcome::Posted),
+        // the server rejected the entry itself, retrying won't help
+        Err(e) if e.status().is_some_and(|s| s.is_client_error()) => Ok(Outcome::Conflict(e.to_string())),
+        Err(e) => Err(e),
+    }
+}
+
+// what is left of the journal after replaying it
+struct Replayed {
+    remaining: Vec<QueuedEntry>,
+    earliest_posted: Option<DateTime<Utc>>,
+    failure: Option<Error>,
+}
+
+// replays the entries in order; the first error leaves that entry and all after it queued
+fn replay_all(queued: Vec<QueuedEntry>, options: &SyncOptions, report: &mut SyncReport, mut replay: impl FnMut(&QueuedEntry) -> Result<Outcome, Error>) -> Replayed {
+    let mut replayed = Replayed { remaining: Vec::new(), earliest_posted: None, failure: None };
+    let mut pending = queued.into_iter();
+    for mut entry in pending.by_ref() {
+        if entry.conflict.is_some() && !options.retry_conflicts {
+            replayed.remaining.push(entry);
+            continue;
+        }
+        match replay(&entry) {
+            Ok(Outcome::Posted) => {
+                report.posted += 1;
+                let start = entry.body.start;
+                replayed.earliest_posted = Some(replayed.earliest_posted.map_or(start, |earliest| earliest.min(start)));
+            }
+            Ok(Outcome::Duplicate) => report.duplicates += 1,
+            Ok(Outcome::Conflict(reason)) => {
+                report.conflicts.push((entry.label.clone(), reason.clone()));
+                if !options.discard_conflicts {
+                    entry.conflict = Some(reason);
+                    replayed.remaining.push(entry);
+                }
+            }
+            Err(e) => {
+                replayed.remaining.push(entry);
+                replayed.failure = Some(e);
+                break;
+            }
+        }
+    }
+    replayed.remaining.extend(pending);
+    replayed
+}
+
+/// Outcome of replaying the journal.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub posted: usize,
+    pub duplicates: usize,
+    /// entries left in the journal, including conflicting ones that were kept
+    pub remaining: usize,
+    /// label and reason of every entry that conflicted during this sync
+    pub conflicts: Vec<(String, String)>,
+    /// set if the sync stopped early, e.g. because we went offline again
+    pub interrupted: Option<String>,
+    /// set if the journal could not be locked or rewritten after the sync
+    pub journal_error: Option<String>,
+}
+
+/// Replays queued entries in order; stops at the first network error so the order is preserved.
+pub fn sync(cfg: &Cfg, options: &SyncOptions) -> Result<SyncReport, Error> {
+    let mut report = SyncReport::default();
+    let _lock = match lock() {
+        Ok(lock) => lock,
+        Err(e) => {
+            report.journal_error = Some(e.to_string());
+            return Ok(report);
+        }
+    };
+    let queued = load();
+    if queued.is_empty() {
+        return Ok(report);
+    }
+    let replayed = replay_all(queued, options, &mut report, |entry| replay(cfg, entry, options));
+    report.remaining = replayed.remaining.len();
+    if let Err(e) = save(&replayed.remaining) {
+        report.journal_error = Some(e.to_string());
+    }
+    if report.posted > 0 || report.duplicates > 0 {
+        crate::cache::invalidate(crate::cache::CacheKind::TimeEntries);
+    }
+    if let Some(start) = replayed.earliest_posted {
+        crate::ledger::forget_since(cfg, cfg.zone.date_of(start));
+    }
+    if let Some(e) = replayed.failure {
+        if report.posted == 0 && report.duplicates == 0 {
+            return Err(e);
+        }
+        report.interrupted = Some(e.to_string());
+    }
+    Ok(report)
+}
+
+/// Flushes the journal after a successful call; conflicting entries wait for an explicit [`sync`].
+/// None if there was nothing to replay.
+pub fn flush(cfg: &Cfg) -> Result<Option<SyncReport>, Error> {
+    if is_empty() || load().iter().all(|entry| entry.conflict.is_some()) {
+        return Ok(None);
+    }
+    sync(cfg, &SyncOptions::default()).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        format!("2024-05-01T{}:00Z", time).parse().unwrap()
+    }
+
+    fn queued(label: &str, start: &str, minutes: i64) -> QueuedEntry {
+        QueuedEntry {
+            queued_at: 0,
+            label: label.to_string(),
+            body: NewTimeEntry {
+                start: at(start),
+                duration: Duration::minutes(minutes),
+                tid: Some("t1".to_string()),
+                description: None,
+                tags: Vec::new(),
+                billable: None,
+            },
+            conflict: None,
+        }
+    }
+
+    fn existing(id: &str, task: &str, start: &str, minutes: i64) -> TimeEntry {
+        let start = at(start);
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "start": start.timestamp_millis(),
+            "end": (start + Duration::minutes(minutes)).timestamp_millis(),
+            "duration": minutes * 60 * 1000,
+            "task": {"id": task, "name": "Task", "status": {"status": "open"}},
+        }))
+        .unwrap()
+    }
+
+    fn outcome(outcome: Option<Outcome>) -> String {
+        match outcome {
+            None => "post".to_string(),
+            Some(Outcome::Posted) => "posted".to_string(),
+            Some(Outcome::Duplicate) => "duplicate".to_string(),
+            Some(Outcome::Conflict(reason)) => reason,
+        }
+    }
+
+    #[test]
+    fn booked_entries_are_duplicates() {
+        let entry = queued("a", "09:00", 60);
+        assert_eq!(outcome(judge(&entry, &[existing("e1", "t1", "09:00", 60)], &SyncOptions::default())), "duplicate");
+        // a few seconds off is still the same booking
+        let mut close = existing("e1", "t1", "09:00", 60);
+        close.start -= Duration::seconds(30);
+        close.duration = close.duration + Duration::seconds(30);
+        assert_eq!(outcome(judge(&entry, &[close], &SyncOptions::default())), "duplicate");
+        // the same time on another task is a conflict
+        assert_eq!(outcome(judge(&entry, &[existing("e2", "t2", "09:00", 60)], &SyncOptions::default())), "overlaps existing entry e2 (Task)");
+    }
+
+    #[test]
+    fn overlaps_conflict_unless_forced() {
+        let entry = queued("a", "09:00", 60);
+        let other = [existing("e2", "t2", "09:30", 60)];
+        assert_eq!(outcome(judge(&entry, &other, &SyncOptions::default())), "overlaps existing entry e2 (Task)");
+        assert_eq!(outcome(judge(&entry, &other, &SyncOptions { force: true, ..Default::default() })), "post");
+        // touching entries don't overlap
+        assert_eq!(outcome(judge(&entry, &[existing("e3", "t2", "10:00", 30)], &SyncOptions::default())), "post");
+    }
+
+    #[test]
+    fn replay_stops_at_the_first_error() {
+        let journal = vec![queued("a", "09:00", 30), queued("b", "10:00", 30), queued("c", "11:00", 30), queued("d", "12:00", 30)];
+        let mut report = SyncReport::default();
+        let mut replayed = Vec::new();
+        let result = replay_all(journal, &SyncOptions::default(), &mut report, |entry| {
+            replayed.push(entry.label.clone());
+            match entry.label.as_str() {
+                "a" => Ok(Outcome::Posted),
+                "b" => Ok(Outcome::Duplicate),
+                _ => Err(Error::Config("offline".to_string())),
+            }
+        });
+        assert_eq!(replayed, ["a", "b", "c"]);
+        assert_eq!((report.posted, report.duplicates), (1, 1));
+        assert_eq!(result.remaining.iter().map(|e| e.label.as_str()).collect::<Vec<&str>>(), ["c", "d"]);
+        assert_eq!(result.earliest_posted, Some(at("09:00")));
+        assert!(result.failure.is_some());
+    }
+
+    #[test]
+    fn conflicts_stay_queued_unless_discarded() {
+        let mut earlier = queued("old", "08:00", 30);
+        earlier.conflict = Some("overlaps".to_string());
+        let conflict = |_: &QueuedEntry| Ok(Outcome::Conflict("overlaps".to_string()));
+
+        let mut report = SyncReport::default();
+        let result = replay_all(vec![earlier, queued("new", "09:00", 30)], &SyncOptions::default(), &mut report, conflict);
+        // conflicts of earlier syncs wait for an explicit retry
+        assert_eq!(report.conflicts, [("new".to_string(), "overlaps".to_string())]);
+        assert_eq!(result.remaining.len(), 2);
+        assert!(result.remaining.iter().all(|e| e.conflict.is_some()));
+
+        let mut report = SyncReport::default();
+        let options = SyncOptions { retry_conflicts: true, discard_conflicts: true, ..Default::default() };
+        let result = replay_all(result.remaining, &options, &mut report, conflict);
+        assert_eq!(report.conflicts.len(), 2);
+        assert!(result.remaining.is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+//! Flextime balance: tracked minus expected hours per day since a start date.
+//!
+//! Tracked hours of closed days (before today) are kept under [`cache_dir`] so only new days are
+//! fetched. Expected hours are always derived from the current schedule, holidays and vacations.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{Datelike, Days, NaiveDate};
+
+use crate::cache::{cache_dir, CacheMode};
+use crate::client::Client;
+use crate::clock::Range;
+use crate::config::Cfg;
+use crate::error::Error;
+use crate::models::hours;
+
+/// Tracked and expected hours of one day.
+#[derive(Debug, Clone)]
+pub struct Day {
+    pub date: NaiveDate,
+    pub tracked: f32,
+    pub expected: f32,
+}
+
+impl Day {
+    pub fn delta(&self) -> f32 {
+        self.tracked - self.expected
+    }
+}
+
+/// Days summed up per week or month; `balance` is the running total at the end of the period.
+#[derive(Debug, Clone)]
+pub struct Period {
+    pub first: NaiveDate,
+    pub tracked: f32,
+    pub expected: f32,
+    pub balance: f32,
+}
+
+impl Period {
+    pub fn delta(&self) -> f32 {
+        self.tracked - self.expected
+    }
+}
+
+fn ledger_path(cfg: &Cfg) -> Option<PathBuf> {
+    Some(cache_dir()?.join("ledger").join(format!("{}.json", cfg.team_id)))
+}
+
+fn load(cfg: &Cfg) -> BTreeMap<NaiveDate, f32> {
+    ledger_path(cfg)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn store(cfg: &Cfg, closed: &BTreeMap<NaiveDate, f32>) {
+    let Some(path) = ledger_path(cfg) else { return };
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Ok(raw) = serde_json::to_string(closed) {
+        let _ = fs::write(path, raw);
+    }
+}
+
+// drops the days from `day` on, true if there were any
+fn forget(closed: &mut BTreeMap<NaiveDate, f32>, day: NaiveDate) -> bool {
+    let before = closed.len();
+    closed.retain(|date, _| *date < day);
+    closed.len() != before
+}
+
+/// Forgets the cached days from `day` on, e.g. after booking time on a past day.
+pub fn forget_since(cfg: &Cfg, day: NaiveDate) {
+    let mut closed = load(cfg);
+    if forget(&mut closed, day) {
+        store(cfg, &closed);
+    }
+}
+
+// the first day from `since` on that isn't closed and cached yet, today at the latest
+fn first_missing(closed: &BTreeMap<NaiveDate, f32>, since: NaiveDate, today: NaiveDate) -> NaiveDate {
+    since.iter_days().take_while(|day| *day < today).find(|day| !closed.contains_key(day)).unwrap_or(today)
+}
+
+// the tracked hours of the days before `first_missing` come from `closed`, the others from
+// `fetched`; days before today are closed from now on
+fn merge(closed: &mut BTreeMap<NaiveDate, f32>, fetched: &BTreeMap<NaiveDate, f32>, since: NaiveDate, first_missing: NaiveDate, today: NaiveDate) -> Vec<(NaiveDate, f32)> {
+    let mut days = Vec::new();
+    for date in since.iter_days().take_while(|day| *day <= today) {
+        let tracked = match closed.get(&date) {
+            Some(tracked) if date < first_missing => *tracked,
+            _ => fetched.get(&date).copied().unwrap_or(0.0),
+        };
+        if date < today {
+            closed.insert(date, tracked);
+        }
+        days.push((date, tracked));
+    }
+    days
+}
+
+/// Tracked and expected hours of every day from `since` to today, today included.
+pub fn days(cfg: &Cfg, since: NaiveDate) -> Result<Vec<Day>, Error> {
+    let today = cfg.zone.today();
+    let mut closed = match cfg.cache_mode {
+        CacheMode::Normal => load(cfg),
+        CacheMode::Refresh | CacheMode::Bypass => BTreeMap::new(),
+    };
+    // everything from the first day we don't know yet is fetched in one windowed request
+    let first_missing = first_missing(&closed, since, today);
+    let entries = Client::new(cfg).time_entries_overlapping(&Range::days(cfg.zone, first_missing, today))?;
+    // entries crossing midnight are split over the days they touch
+    let mut fetched: BTreeMap<NaiveDate, f32> = BTreeMap::new();
+    for entry in &entries {
+        let first = cfg.zone.date_of(entry.start).max(first_missing);
+        let last = cfg.zone.date_of(entry.end_or_now()).min(today);
+        for date in first.iter_days().take_while(|day| *day <= last) {
+            *fetched.entry(date).or_default() += hours(entry.elapsed_in(&Range::day(cfg.zone, date)));
+        }
+    }
+    let days = merge(&mut closed, &fetched, since, first_missing, today)
+        .into_iter()
+        .map(|(date, tracked)| Day {
+            date,
+            tracked,
+            expected: cfg.quota(date),
+        })
+        .collect();
+    if cfg.cache_mode != CacheMode::Bypass {
+        store(cfg, &closed);
+    }
+    Ok(days)
+}
+
+/// Monday of the week `day` is in.
+pub fn week_of(day: NaiveDate) -> NaiveDate {
+    day - Days::new(day.weekday().num_days_from_monday() as u64)
+}
+
+/// First day of the month `day` is in.
+pub fn month_of(day: NaiveDate) -> NaiveDate {
+    day.with_day(1).unwrap()
+}
+
+/// Sums days into periods starting on `period_of(day)`, with the running balance.
+pub fn group(days: &[Day], period_of: fn(NaiveDate) -> NaiveDate) -> Vec<Period> {
+    let mut periods: Vec<Period> = Vec::new();
+    let mut balance = 0.0;
+    for day in days {
+        balance += day.delta();
+        let first = period_of(day.date);
+        match periods.last_mut() {
+            Some(period) if period.first == first => {
+                period.tracked += day.tracked;
+                period.expected += day.expected;
+                period.balance = balance;
+            }
+            _ => periods.push(Period {
+                first,
+                tracked: day.tracked,
+                expected: day.expected,
+                balance,
+            }),
+        }
+    }
+    periods
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    fn day(date: NaiveDate, tracked: f32, expected: f32) -> Day {
+        Day { date, tracked, expected }
+    }
+
+    #[test]
+    fn closed_days_are_not_fetched_again() {
+        let mut closed = BTreeMap::from([(date(1), 8.0), (date(2), 6.0)]);
+        assert_eq!(first_missing(&closed, date(1), date(4)), date(3));
+        assert_eq!(first_missing(&closed, date(1), date(2)), date(2));
+        let fetched = BTreeMap::from([(date(2), 9.0), (date(3), 7.0), (date(4), 2.0)]);
+        let days = merge(&mut closed, &fetched, date(1), date(3), date(4));
+        assert_eq!(days, [(date(1), 8.0), (date(2), 6.0), (date(3), 7.0), (date(4), 2.0)]);
+        // today can still change, it is fetched again next time
+        assert_eq!(closed, BTreeMap::from([(date(1), 8.0), (date(2), 6.0), (date(3), 7.0)]));
+    }
+
+    #[test]
+    fn days_without_entries_are_closed_with_nothing_tracked() {
+        let mut closed = BTreeMap::new();
+        let days = merge(&mut closed, &BTreeMap::from([(date(2), 4.0)]), date(1), date(1), date(3));
+        assert_eq!(days, [(date(1), 0.0), (date(2), 4.0), (date(3), 0.0)]);
+        assert_eq!(closed.len(), 2);
+    }
+
+    #[test]
+    fn forgetting_drops_the_day_and_later_ones_only() {
+        let mut closed = BTreeMap::from([(date(1), 8.0), (date(2), 6.0), (date(3), 7.0)]);
+        assert!(forget(&mut closed, date(2)));
+        assert_eq!(closed, BTreeMap::from([(date(1), 8.0)]));
+        assert!(!forget(&mut closed, date(5)));
+        assert_eq!(closed.len(), 1);
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // 2024-05-01 is a Wednesday, the 6th the Monday after
+        let days = [day(date(1), 8.0, 8.0), day(date(3), 9.0, 8.0), day(date(6), 6.0, 8.0), day(date(7), 8.0, 8.0)];
+        let weeks = group(&days, week_of);
+        assert_eq!(weeks.iter().map(|w| (w.first, w.tracked, w.expected, w.balance)).collect::<Vec<_>>(), [(date(6) - Days::new(7), 17.0, 16.0, 1.0), (date(6), 14.0, 16.0, -1.0)]);
+    }
+
+    #[test]
+    fn months_carry_the_running_balance() {
+        let april = NaiveDate::from_ymd_opt(2024, 4, 30).unwrap();
+        let days = [day(april, 10.0, 8.0), day(date(1), 7.0, 8.0), day(date(31), 8.0, 8.0)];
+        let months = group(&days, month_of);
+        assert_eq!(months.iter().map(|m| (m.first, m.delta(), m.balance)).collect::<Vec<_>>(), [(month_of(april), 2.0, 2.0), (date(1), -1.0, 1.0)]);
+    }
+}
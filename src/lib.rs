@@ -0,0 +1,33 @@
+//! ClickUp time tracking and task helpers behind the `cupcli` binary.
+//!
+//! [`Client`] wraps the ClickUp API v2 endpoints for time entries, timers, tasks, comments and
+//! the space/folder/list hierarchy and returns the [`models`] as data or an [`Error`]. [`cache`]
+//! serves the same resources from disk with per-kind TTLs and [`journal`] queues time entries
+//! while offline.
+//! [`calendar`] knows the holidays and vacations that expected hours skip, [`ledger`] sums up
+//! the flextime balance and [`remind`] notifies when nothing is tracked. [`pomodoro`] runs the
+//! timer in work/break cycles.
+//! Everything is configured through a [`Cfg`], usually read from `~/.config/cupcli/cfg` and the
+//! [`project`]'s `.cupcli.toml` by [`config::build_cfg`].
+
+pub mod cache;
+pub mod calendar;
+pub mod check;
+pub mod client;
+pub mod clock;
+pub mod config;
+pub mod error;
+pub mod git;
+pub mod journal;
+pub mod ledger;
+pub mod models;
+pub mod pomodoro;
+pub mod project;
+pub mod remind;
+pub mod search;
+pub mod utils;
+
+pub use client::{AsyncClient, Client};
+pub use config::Cfg;
+pub use error::Error;
+pub use models::{Comment, Hierarchy, Member, Status, Task, TimeEntry};
@@ -1,38 +1,128 @@
 mod api;
 mod args;
-mod config;
-mod utils;
+mod daemon;
+mod display;
+mod picker;
+mod prompt;
+mod tui;
 
-use std::collections::HashSet;
 use std::env;
 
-use itertools::{Itertools, Either};
+use chrono::{Days, NaiveDate};
 
-use crate::api::{task_get, time_get, time_track};
+use crate::api::*;
 use crate::args::*;
-use crate::config::build_cfg;
-
-// wrap main logic inside of run so we can print ArgErrors to stdout in readable format
-// if we return Result<(), ArgError> from main, the error is printed in Debug format
-fn run() -> Result<(), ArgError> {
-    let cfg = build_cfg();
-    let mut args: Vec<String> = env::args().skip(1).collect();
-    if args.len() < 2 {
+use crate::display::ERROR;
+use cupcli::cache::{CacheKind, CacheMode};
+use cupcli::config::build_cfg;
+use cupcli::git;
+use cupcli::Cfg;
+use cupcli::journal::{self, SyncOptions};
+
+// a day as YYYY-MM-DD, `today` or `yesterday`
+fn parse_day(cfg: &Cfg, arg: &str) -> Result<NaiveDate, ArgError> {
+    match arg {
+        "today" => Ok(cfg.zone.today()),
+        "yesterday" => Ok(cfg.zone.today() - Days::new(1)),
+        _ => Ok(arg.parse::<NaiveDate>()?),
+    }
+}
+
+// no day means today, one day or `from..to` or two days give an inclusive range
+fn parse_days(cfg: &Cfg, args: &[&str]) -> Result<(NaiveDate, NaiveDate), ArgError> {
+    let (first, last) = match args {
+        [] => (cfg.zone.today(), cfg.zone.today()),
+        [range] => match range.split_once("..") {
+            Some((first, last)) => (parse_day(cfg, first)?, parse_day(cfg, last)?),
+            None => (parse_day(cfg, range)?, parse_day(cfg, range)?),
+        },
+        [first, last] => (parse_day(cfg, first)?, parse_day(cfg, last)?),
+        _ => return Err(ArgError::ArgCount("Expected a day or a range of days".to_string())),
+    };
+    if last < first {
+        return Err(ArgError::ArgValue(format!("The range ends before it starts: {} to {}", first, last)));
+    }
+    Ok((first, last))
+}
+
+// flags of timetrack and timer start followed by a value, a `?` after them isn't a task id
+fn takes_value(arg: &str) -> bool {
+    matches!(arg, "-D" | "--description" | "-t" | "--tag" | "-d" | "--duration")
+}
+
+/// The index of the task id argument to pick interactively, counted without the global flags:
+/// a `?` in place of the id, or where the left out id of a command that needs one goes.
+pub fn wants_picker(args: &[String]) -> Option<usize> {
+    let args: Vec<&str> = args.iter().map(String::as_str).filter(|arg| !matches!(*arg, "--debug" | "--no-cache" | "--refresh")).collect();
+    match args[..] {
+        ["timetrack", ..] => (1..args.len()).find(|&i| args[i] == "?" && !takes_value(args[i - 1])),
+        ["timer", "start", ..] => (2..args.len()).find(|&i| args[i] == "?" && !takes_value(args[i - 1])),
+        ["timer", "pomodoro", "?", ..] | ["task", "status" | "comment" | "comments", "?", ..] => Some(2),
+        // only the flags of the plan, or only the status or the text of a comment, which has to be quoted
+        ["timer", "pomodoro"] | ["task", "comments"] | ["task", "status" | "comment", _] => Some(2),
+        ["timer", "pomodoro", flag, ..] if flag.starts_with('-') => Some(2),
+        _ => None,
+    }
+}
+
+// what a command prints: Ok goes to stdout, Err to stderr
+pub type Reply = Result<String, String>;
+
+/// Runs a command line and returns what it prints, used directly and by the daemon.
+pub fn execute(cfg: Cfg, args: Vec<String>) -> Reply {
+    // ArgErrors are printed in their readable Display format, not in Debug format
+    run(cfg, args).unwrap_or_else(|e| Err(e.to_string()))
+}
+
+fn run(mut cfg: Cfg, mut args: Vec<String>) -> Result<Reply, ArgError> {
+    // global flags may appear anywhere, strip them before dispatching
+    args.retain(|arg| match arg.as_str() {
+        "--debug" => {
+            cfg.debug = true;
+            false
+        }
+        "--no-cache" => {
+            cfg.cache_mode = CacheMode::Bypass;
+            false
+        }
+        "--refresh" => {
+            cfg.cache_mode = CacheMode::Refresh;
+            false
+        }
+        _ => true,
+    });
+    if args.is_empty() {
         return Err(ArgError::ArgCount(
-            "Expects at least two arguments".to_string(),
+            "Expects at least one argument".to_string(),
         ));
     }
+    if let Some(index) = wants_picker(&args) {
+        let task = match picker::pick_task(&cfg) {
+            Ok(Some(task)) => task,
+            Ok(None) => return Ok(Ok(String::new())),
+            Err(e) => return Ok(Err(e)),
+        };
+        if args.get(index).is_some_and(|arg| arg == "?") {
+            args[index] = task.id;
+        } else {
+            args.insert(index, task.id);
+        }
+    }
+    let sub = args.get(1).cloned().unwrap_or_default();
+    // local-only and deliberately offline calls must not trigger the automatic journal flush
+    let skip_flush = matches!(args[0].as_str(), "cache" | "complete" | "vacation") || (args[0] == "git" && sub != "post-commit") || args.iter().any(|a| a == "-o" || a == "--offline");
     let res = match args[0].as_str() {
         "timeget" => {
-            let arg: TimeGet = match args[1].as_str() {
+            let arg: TimeGet = match sub.as_str() {
                 "today" => TimeGet::Today,
                 "week" => TimeGet::Week,
+                "month" => TimeGet::Month,
                 "yesterday" => TimeGet::Yesterday,
-                _ => return Err(ArgError::ArgValue("Invalid second argument for first argument 'timeget'. Only 'today', 'week' and 'yesterday' are valid!".to_string()))
+                _ => return Err(ArgError::ArgValue("Invalid second argument for first argument 'timeget'. Only 'today', 'week', 'month' and 'yesterday' are valid!".to_string()))
             };
             time_get(arg, &cfg)
         } "taskget" => {
-            let arg: TaskGet = match args[1].as_str() {
+            let arg: TaskGet = match sub.as_str() {
                 "last" => TaskGet::Last,
                 "sprint" => {
                     TaskGet::Sprint
@@ -41,84 +131,415 @@ fn run() -> Result<(), ArgError> {
             };
             task_get(arg, &cfg)
         }
-        "timetrack" => {
-            if args.len() < 2 {
-                return Err(ArgError::ArgCount(
-                    "Invalid number of arguments for 'timetrack'!".to_string(),
-                ));
-            }
-
-            args.remove(0); // not optimal because we shift all elements by 1, but it's fine for now
-            let valid_flags: HashSet<&str> = HashSet::from(["-d", "--description", "-D", "--duration"]);
-
-            // separate the args vec into flags / options and the remaining args
-            let (args, flags): (Vec<&str>, Vec<Result<TimeTrackFlag, ArgError>>) = args.chunks(2).partition_map(|chunk| {
-                if let Some(v) = valid_flags.get(&chunk[0].as_str()) {
-                    match *v {
-                        "-D" | "--description" => Either::Right(Ok(TimeTrackFlag::Description(chunk[1].as_str()))),
-                        "-d" | "--duration" => {
-                            let parsed_v = chunk[1].parse::<u32>();
-                            match parsed_v {
-                                Ok(v) => Either::Right(Ok(TimeTrackFlag::Duration(v))),
-                                Err(_) => Either::Right(Err(ArgError::ArgValue(format!("Invalid value for flag {}", chunk[0])))),
+        "task" => {
+            match sub.as_str() {
+                "search" => {
+                    let mut query: Vec<&str> = Vec::new();
+                    let mut flags: Vec<TaskSearchFlag> = Vec::new();
+                    let mut rest = args[2..].iter();
+                    while let Some(arg) = rest.next() {
+                        match arg.as_str() {
+                            "-D" | "--description" => flags.push(TaskSearchFlag::Description),
+                            "-n" | "--limit" => {
+                                let limit = rest.next().ok_or(ArgError::ArgValue(format!("Missing value for flag {}", arg)))?.parse::<usize>()?;
+                                flags.push(TaskSearchFlag::Limit(limit));
                             }
-                        },
-                        _ => unreachable!()
-
-                    } // valid flag
-                } else if chunk[0].starts_with("-") {
-                    Either::Right(Err(ArgError::ArgValue(format!("Invalid flag: {}", chunk[0])))) // invalid flag
+                            _ if arg.starts_with('-') => return Err(ArgError::ArgValue(format!("Invalid flag: {}", arg))),
+                            _ => query.push(arg),
+                        }
+                    }
+                    if query.is_empty() {
+                        return Err(ArgError::ArgCount("'task search' expects a query".to_string()));
+                    }
+                    task_search(TaskSearch { query: query.join(" "), flags }, &cfg)
+                }
+                "status" => {
+                    if args.len() < 4 {
+                        return Err(ArgError::ArgCount("'task status' expects a task id and a status".to_string()));
+                    }
+                    task_status(&cfg, &args[2], &args[3..].join(" "))
                 }
-                else {
-                    Either::Left(chunk[0].as_str()) // positional arg
+                "list" => {
+                    let mut list_ids: Vec<&str> = args[2..].iter().map(String::as_str).collect();
+                    if list_ids.is_empty() {
+                        list_ids.push(&cfg.list_id);
+                    }
+                    tasks_list(&cfg, &list_ids)
                 }
-            });
-            let flag_err = flags.iter().find_map(|f| {
-                match f {
-                    Ok(_) => None,
-                    Err(e) => Some(e)
+                "comments" => {
+                    let Some(task_id) = args.get(2) else {
+                        return Err(ArgError::ArgCount("'task comments' expects a task id".to_string()));
+                    };
+                    task_comments(&cfg, task_id)
+                }
+                "comment" => {
+                    if args.len() < 4 {
+                        return Err(ArgError::ArgCount("'task comment' expects a task id and a text".to_string()));
+                    }
+                    task_create_comment(&cfg, &args[2], &args[3..].join(" "))
+                }
+                _ => return Err(ArgError::ArgValue("Invalid second argument for first argument 'task'. Only 'search', 'status', 'list', 'comments' and 'comment' are valid!".to_string()))
+            }
+        }
+        "cache" => {
+            match sub.as_str() {
+                "clear" => {
+                    let kinds = args[2..]
+                        .iter()
+                        .map(|name| CacheKind::from_name(name).ok_or(ArgError::ArgValue(format!("Unknown cache kind: {}", name))))
+                        .collect::<Result<Vec<CacheKind>, ArgError>>()?;
+                    Ok(cache_clear(&kinds))
+                }
+                _ => return Err(ArgError::ArgValue("Invalid second argument for first argument 'cache'. Only 'clear' is valid!".to_string()))
+            }
+        }
+        "complete" => complete(&cfg, &sub),
+        "timetrack" => {
+            let mut positional: Vec<&str> = Vec::new();
+            let mut flags: Vec<TimeTrackFlag> = Vec::new();
+            let mut rest = args[1..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "-D" | "--description" => flags.push(TimeTrackFlag::Description(rest.next().ok_or(ArgError::ArgValue(format!("Missing value for flag {}", arg)))?.as_str())),
+                    "-t" | "--tag" => flags.push(TimeTrackFlag::Tag(rest.next().ok_or(ArgError::ArgValue(format!("Missing value for flag {}", arg)))?.as_str())),
+                    "-d" | "--duration" => {
+                        let value = rest.next().ok_or(ArgError::ArgValue(format!("Missing value for flag {}", arg)))?;
+                        let minutes = value.parse::<u32>().map_err(|_| ArgError::ArgValue(format!("Invalid value for flag {}", arg)))?;
+                        flags.push(TimeTrackFlag::Duration(minutes));
+                    }
+                    "-o" | "--offline" => flags.push(TimeTrackFlag::Offline),
+                    "--billable" => flags.push(TimeTrackFlag::Billable(true)),
+                    "--no-billable" => flags.push(TimeTrackFlag::Billable(false)),
+                    _ if arg.starts_with('-') => return Err(ArgError::ArgValue(format!("Invalid flag: {}", arg))),
+                    _ => positional.push(arg),
                 }
-            });
-            if flag_err.is_some() {
-                return Err(flag_err.unwrap().clone())
             }
-            let flags = flags.into_iter().map(|f| f.unwrap()).collect();
+            if positional.len() > 1 {
+                return Err(ArgError::ArgCount(format!("'timetrack' expects at most one task id, 'last' or 'branch', got: {}", positional.join(" "))));
+            }
+            let args = positional;
 
             let args = if args.is_empty() {
-               TimeTrack {
-                mode: TimeTrackMode::Free,
-                flags
-               }
+                // inside a git repository the branch names the task, if it names one
+                let mode = if git::branch_task_id(&cfg).is_ok() {
+                    TimeTrackMode::Branch
+                } else if flags.is_empty() {
+                    return Err(ArgError::ArgCount("Invalid number of arguments for 'timetrack'!".to_string()));
+                } else {
+                    TimeTrackMode::Free
+                };
+                TimeTrack { mode, flags }
             } else {
                 match args[0] {
                     "last" => TimeTrack {
                         mode: TimeTrackMode::Last,
                         flags,
                     },
+                    "branch" => TimeTrack {
+                        mode: TimeTrackMode::Branch,
+                        flags,
+                    },
                     _ => TimeTrack {
-                        mode: TimeTrackMode::TaskId(&args[0]),
+                        mode: TimeTrackMode::TaskId(args[0]),
                         flags,
                     },
                 }
             };
             time_track(args, &cfg)
         }
+        "timer" => {
+            match sub.as_str() {
+                "start" => {
+                    let mut task_id = None;
+                    let mut flags = Vec::new();
+                    let mut rest = args[2..].iter();
+                    while let Some(arg) = rest.next() {
+                        match arg.as_str() {
+                            "-D" | "--description" => flags.push(TimeTrackFlag::Description(rest.next().ok_or(ArgError::ArgValue(format!("Missing value for flag {}", arg)))?.as_str())),
+                            "-t" | "--tag" => flags.push(TimeTrackFlag::Tag(rest.next().ok_or(ArgError::ArgValue(format!("Missing value for flag {}", arg)))?.as_str())),
+                            "--billable" => flags.push(TimeTrackFlag::Billable(true)),
+                            "--no-billable" => flags.push(TimeTrackFlag::Billable(false)),
+                            _ if arg.starts_with('-') => return Err(ArgError::ArgValue(format!("Invalid flag: {}", arg))),
+                            _ => task_id = Some(arg.as_str()),
+                        }
+                    }
+                    // inside a git repository the branch names the task, if it names one
+                    if task_id.is_none() && git::branch_task_id(&cfg).is_ok() {
+                        task_id = Some("branch");
+                    }
+                    timer_start(&cfg, task_id, flags)
+                }
+                "stop" => timer_stop(&cfg),
+                "current" => timer_current(&cfg),
+                "pomodoro" => {
+                    let cmd = match args.get(2).map(String::as_str) {
+                        None => return Err(ArgError::ArgCount("'timer pomodoro' expects a task id, 'last', 'resume' or 'stop'".to_string())),
+                        Some("resume") => PomodoroCmd::Resume,
+                        Some("stop") => PomodoroCmd::Stop,
+                        Some(task) => {
+                            let mut plan = cfg.pomodoro;
+                            let mut rest = args[3..].iter();
+                            while let Some(arg) = rest.next() {
+                                let value = rest.next().ok_or(ArgError::ArgValue(format!("Missing value for flag {}", arg)))?;
+                                match arg.as_str() {
+                                    "-w" | "--work" => plan.work_mins = value.parse::<i64>()?,
+                                    "-b" | "--break" => plan.break_mins = value.parse::<i64>()?,
+                                    "-l" | "--long-break" => plan.long_break_mins = value.parse::<i64>()?,
+                                    "-n" | "--rounds" => plan.rounds = value.parse::<u32>()?,
+                                    _ => return Err(ArgError::ArgValue(format!("Invalid flag: {}", arg))),
+                                }
+                            }
+                            if plan.work_mins <= 0 || plan.break_mins <= 0 || plan.long_break_mins <= 0 || plan.rounds == 0 {
+                                return Err(ArgError::ArgValue("Pomodoro lengths and rounds must be positive".to_string()));
+                            }
+                            PomodoroCmd::Start { task, plan }
+                        }
+                    };
+                    pomodoro(&cfg, cmd)
+                }
+                _ => return Err(ArgError::ArgValue("Invalid second argument for first argument 'timer'. Only 'start', 'stop', 'current' and 'pomodoro' are valid!".to_string()))
+            }
+        }
+        "statusbar" => {
+            let format = match args.get(1).map(String::as_str) {
+                None | Some("waybar") => StatusbarFormat::Waybar,
+                Some("i3blocks") => StatusbarFormat::I3blocks,
+                Some("polybar") => StatusbarFormat::Polybar,
+                Some(other) => return Err(ArgError::ArgValue(format!("Unknown statusbar format '{}', valid are: waybar, i3blocks, polybar", other))),
+            };
+            // polled every few seconds: never flush the journal from here and always print something
+            return Ok(Ok(statusbar(&cfg, format)));
+        }
+        "remind" => remind(&cfg),
+        "tui" => Ok(tui::tui(&cfg)),
+        "git" => {
+            match sub.as_str() {
+                "install-hooks" => {
+                    let force = match args.get(2).map(String::as_str) {
+                        None => false,
+                        Some("-f" | "--force") => true,
+                        Some(flag) => return Err(ArgError::ArgValue(format!("Invalid flag: {}", flag))),
+                    };
+                    Ok(git_install_hooks(force))
+                }
+                // called by the installed hooks with git's arguments
+                "prepare-commit-msg" => {
+                    let Some(file) = args.get(2) else {
+                        return Err(ArgError::ArgCount("'git prepare-commit-msg' expects the commit message file".to_string()));
+                    };
+                    Ok(git_prepare_commit_msg(&cfg, file, args.get(3).map(String::as_str)))
+                }
+                "post-commit" => git_post_commit(&cfg),
+                _ => return Err(ArgError::ArgValue("Invalid second argument for first argument 'git'. Only 'install-hooks', 'prepare-commit-msg' and 'post-commit' are valid!".to_string()))
+            }
+        }
+        "time" => {
+            match sub.as_str() {
+                "check" => {
+                    let mut report_only = false;
+                    let mut days = Vec::new();
+                    for arg in &args[2..] {
+                        match arg.as_str() {
+                            "-r" | "--report" => report_only = true,
+                            _ if arg.starts_with('-') => return Err(ArgError::ArgValue(format!("Invalid flag: {}", arg))),
+                            _ => days.push(arg.as_str()),
+                        }
+                    }
+                    let (first, last) = parse_days(&cfg, &days)?;
+                    time_check(&cfg, first, last, report_only)
+                }
+                "fill" => {
+                    let days: Vec<&str> = args[2..].iter().map(String::as_str).collect();
+                    let (first, last) = parse_days(&cfg, &days)?;
+                    time_fill(&cfg, first, last)
+                }
+                _ => return Err(ArgError::ArgValue("Invalid second argument for first argument 'time'. Only 'check' and 'fill' are valid!".to_string()))
+            }
+        }
+        "balance" => {
+            let mut args_balance = Balance { since: None, view: BalanceView::Weeks };
+            let mut rest = args[1..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "-s" | "--since" => args_balance.since = Some(rest.next().ok_or(ArgError::ArgValue(format!("Missing value for flag {}", arg)))?.parse::<NaiveDate>()?),
+                    "-d" | "--days" => args_balance.view = BalanceView::Days,
+                    "-w" | "--weeks" => args_balance.view = BalanceView::Weeks,
+                    "-m" | "--months" => args_balance.view = BalanceView::Months,
+                    _ => return Err(ArgError::ArgValue(format!("Invalid flag: {}", arg))),
+                }
+            }
+            balance(&cfg, args_balance)
+        }
+        "vacation" => {
+            let cmd = match sub.as_str() {
+                "add" => {
+                    if args.len() != 4 {
+                        return Err(ArgError::ArgCount("'vacation add' expects the first and the last day, e.g. 2024-08-05 2024-08-16".to_string()));
+                    }
+                    VacationCmd::Add(args[2].parse::<NaiveDate>()?, args[3].parse::<NaiveDate>()?)
+                }
+                "list" => VacationCmd::List,
+                "remove" => {
+                    let Some(from) = args.get(2) else {
+                        return Err(ArgError::ArgCount("'vacation remove' expects the first day of the vacation".to_string()));
+                    };
+                    VacationCmd::Remove(from.parse::<NaiveDate>()?)
+                }
+                _ => return Err(ArgError::ArgValue("Invalid second argument for first argument 'vacation'. Only 'add', 'list' and 'remove' are valid!".to_string()))
+            };
+            Ok(vacation(&cfg, cmd))
+        }
+        "tag" => {
+            let cmd = match sub.as_str() {
+                "list" => TagCmd::List,
+                "create" => {
+                    let mut name = None;
+                    let (mut fg, mut bg) = (None, None);
+                    let mut entry_ids = Vec::new();
+                    let mut rest = args[2..].iter();
+                    while let Some(arg) = rest.next() {
+                        match arg.as_str() {
+                            "--fg" => fg = Some(rest.next().ok_or(ArgError::ArgValue(format!("Missing value for flag {}", arg)))?.as_str()),
+                            "--bg" => bg = Some(rest.next().ok_or(ArgError::ArgValue(format!("Missing value for flag {}", arg)))?.as_str()),
+                            _ if arg.starts_with('-') => return Err(ArgError::ArgValue(format!("Invalid flag: {}", arg))),
+                            _ if name.is_none() => name = Some(arg.as_str()),
+                            _ => entry_ids.push(arg.as_str()),
+                        }
+                    }
+                    // ClickUp only knows tags attached to entries, the entries have to be named
+                    let (Some(name), false) = (name, entry_ids.is_empty()) else {
+                        return Err(ArgError::ArgCount("'tag create' expects a tag name and the ids of the time entries to tag".to_string()));
+                    };
+                    TagCmd::Create { name, fg, bg, entry_ids }
+                }
+                "rename" => {
+                    if args.len() != 4 {
+                        return Err(ArgError::ArgCount("'tag rename' expects the current and the new tag name".to_string()));
+                    }
+                    TagCmd::Rename { name: &args[2], new_name: &args[3] }
+                }
+                "delete" => {
+                    let from = match args.get(3).map(String::as_str) {
+                        Some("-s" | "--since") => {
+                            let Some(day) = args.get(4).filter(|_| args.len() == 5) else {
+                                return Err(ArgError::ArgCount("'tag delete <name> --since' expects exactly one day".to_string()));
+                            };
+                            TagScope::Since(parse_day(&cfg, day)?)
+                        }
+                        Some(_) => TagScope::Entries(args[3..].iter().map(String::as_str).collect()),
+                        None => return Err(ArgError::ArgCount("'tag delete' expects a tag name and time entry ids or --since <day>".to_string())),
+                    };
+                    TagCmd::Delete { name: &args[2], from }
+                }
+                _ => return Err(ArgError::ArgValue("Invalid second argument for first argument 'tag'. Only 'list', 'create', 'rename' and 'delete' are valid!".to_string()))
+            };
+            tag(&cfg, cmd)
+        }
+        "sync" => {
+            let mut options = SyncOptions {
+                retry_conflicts: true,
+                ..Default::default()
+            };
+            for flag in &args[1..] {
+                match flag.as_str() {
+                    "-f" | "--force" => options.force = true,
+                    "--discard-conflicts" => options.discard_conflicts = true,
+                    "-l" | "--list" => return Ok(Ok(sync_list())),
+                    _ => return Err(ArgError::ArgValue(format!("Invalid flag: {}", flag))),
+                }
+            }
+            // sync handles the journal itself, skip the automatic flush below
+            return Ok(sync(&cfg, &options).map_err(|e| format!("[REQUEST ERROR] {}", e)));
+        }
         _ => {
             return Err(ArgError::ArgValue(
-                "Invalid first argument! Only 'timeget', 'taskget' and 'timetrack' are valid!"
+                "Invalid first argument! Only 'timeget', 'taskget', 'task', 'timetrack', 'timer', 'time', 'statusbar', 'remind', 'git', 'tui', 'tag', 'vacation', 'balance', 'cache', 'complete', 'sync' and 'daemon' are valid!"
                     .to_string(),
             ))
         }
     };
     match res {
-        Ok(res) => println!("{}", res),
-        Err(e) => eprintln!("[REQUEST ERROR] {}", e),
-    };
-    Ok(())
+        Ok(mut res) => {
+            // we just reached ClickUp (or at least didn't fail), replay what piled up while offline
+            if !skip_flush {
+                match journal::flush(&cfg) {
+                    Ok(Some(report)) => {
+                        res.push('\n');
+                        res.push_str(&fmt_sync_report(&report));
+                    }
+                    Ok(None) => {}
+                    // the command itself went through, its output stays as it is
+                    Err(e) => eprintln!("[WARNING] Could not flush the offline journal: {}", e),
+                }
+            }
+            Ok(Ok(res))
+        }
+        Err(e) => Ok(Err(format!("[REQUEST ERROR] {}", e))),
+    }
+}
+
+/// Prints the settings the cfg ignored to stderr.
+pub fn warn(cfg: &Cfg) {
+    for warning in &cfg.warnings {
+        eprintln!("[WARNING] {}", warning);
+    }
 }
 
 fn main() {
-    if let Err(e) = run() {
-        eprintln!("{}", e)
+    let args: Vec<String> = env::args().skip(1).collect();
+    let reply = match args.first().map(String::as_str) {
+        // the daemon commands manage the daemon, they are never forwarded to it
+        Some("daemon") => daemon::command(&args[1..]),
+        _ => daemon::forward(&args).unwrap_or_else(|| match build_cfg() {
+            Ok(cfg) => {
+                warn(&cfg);
+                execute(cfg, args)
+            }
+            Err(e) => Err(format!("{} {}", ERROR, e)),
+        }),
+    };
+    match reply {
+        // quiet commands like `remind` must not print an empty line, cron would mail it
+        Ok(out) if out.is_empty() => {}
+        Ok(out) => println!("{}", out),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn picks(line: &str) -> Option<usize> {
+        wants_picker(&line.split(' ').map(str::to_string).collect::<Vec<String>>())
+    }
+
+    #[test]
+    fn question_marks_in_place_of_the_id() {
+        assert_eq!(picks("timetrack ? -d 30"), Some(1));
+        assert_eq!(picks("timetrack -D ? -d 30 ?"), Some(5));
+        assert_eq!(picks("timer start --no-cache ? -t deep"), Some(2));
+        assert_eq!(picks("task status ? done"), Some(2));
+        assert_eq!(picks("timer pomodoro ? -w 50"), Some(2));
+    }
+
+    #[test]
+    fn left_out_ids() {
+        assert_eq!(picks("task comments"), Some(2));
+        assert_eq!(picks("task status done"), Some(2));
+        assert_eq!(picks("task comment Reviewed"), Some(2));
+        assert_eq!(picks("timer pomodoro"), Some(2));
+        assert_eq!(picks("timer pomodoro -w 50 -n 2"), Some(2));
+    }
+
+    #[test]
+    fn given_ids_and_other_commands_pick_nothing() {
+        assert_eq!(picks("task comments 86abc123"), None);
+        assert_eq!(picks("task status 86abc123 done"), None);
+        assert_eq!(picks("task comment 86abc123 Reviewed"), None);
+        assert_eq!(picks("timer pomodoro resume"), None);
+        assert_eq!(picks("timer pomodoro 86abc123 -w 50"), None);
+        assert_eq!(picks("timetrack -D ? -d 30"), None);
+        assert_eq!(picks("task list"), None);
     }
 }
@@ -0,0 +1,509 @@
+//! Serde models of the ClickUp API v2 resources used by cupcli.
+//!
+//! ClickUp sends timestamps and durations as millisecond strings; the models expose them as
+//! [`DateTime<Utc>`] and [`Duration`] through the deserializers in [`ms`].
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::Range;
+
+/// (De)serializers for millisecond timestamps and durations. Both strings and numbers are
+/// accepted when reading, numbers are written, which is what ClickUp expects in request bodies.
+pub mod ms {
+    use chrono::{DateTime, Duration, Utc};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Int(i64),
+        Str(String),
+    }
+
+    fn raw_ms<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+        match Option::<Raw>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(Raw::Int(ms)) => Ok(Some(ms)),
+            Some(Raw::Str(s)) if s.is_empty() => Ok(None),
+            Some(Raw::Str(s)) => s.parse::<i64>().map(Some).map_err(D::Error::custom),
+        }
+    }
+
+    fn to_datetime<E: Error>(ms: i64) -> Result<DateTime<Utc>, E> {
+        DateTime::from_timestamp(ms.div_euclid(1000), (ms.rem_euclid(1000) * 1_000_000) as u32).ok_or_else(|| E::custom(format!("timestamp out of range: {}", ms)))
+    }
+
+    pub mod timestamp {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(dt: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_i64(dt.timestamp_millis())
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+            let ms = raw_ms(deserializer)?.ok_or_else(|| D::Error::custom("missing timestamp"))?;
+            to_datetime(ms)
+        }
+    }
+
+    pub mod opt_timestamp {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(dt: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> {
+            match dt {
+                Some(dt) => serializer.serialize_i64(dt.timestamp_millis()),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+            raw_ms(deserializer)?.map(to_datetime).transpose()
+        }
+    }
+
+    pub mod duration {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_i64(duration.num_milliseconds())
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+            Ok(Duration::milliseconds(raw_ms(deserializer)?.unwrap_or(0)))
+        }
+    }
+}
+
+/// Hours as a fraction, the unit all quota math and output uses.
+pub fn hours(duration: Duration) -> f32 {
+    duration.num_milliseconds() as f32 / 1000f32 / 60f32 / 60f32
+}
+
+/// Response wrapper of `GET /team/{team_id}/time_entries`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TimeEntries {
+    pub data: Vec<TimeEntry>,
+}
+
+/// Response wrapper of endpoints returning a single, possibly missing time entry,
+/// e.g. the currently running timer.
+#[derive(Debug, Deserialize)]
+pub struct TimeEntryResponse {
+    pub data: Option<TimeEntry>,
+}
+
+/// A tracked time entry. A running timer has a negative duration and no end.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TimeEntry {
+    pub id: String,
+    #[serde(default)]
+    pub task: Option<Task>,
+    /// workspace (team) id
+    #[serde(default)]
+    pub wid: Option<String>,
+    #[serde(default)]
+    pub user: Option<Member>,
+    #[serde(default)]
+    pub billable: bool,
+    #[serde(with = "ms::timestamp")]
+    pub start: DateTime<Utc>,
+    #[serde(with = "ms::opt_timestamp", default)]
+    pub end: Option<DateTime<Utc>>,
+    #[serde(with = "ms::duration")]
+    pub duration: Duration,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    /// where the entry was created, e.g. "clickup" or "api"
+    #[serde(default)]
+    pub source: Option<String>,
+    /// last modification
+    #[serde(with = "ms::opt_timestamp", default)]
+    pub at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub task_url: Option<String>,
+}
+
+impl TimeEntry {
+    pub fn is_running(&self) -> bool {
+        self.duration < Duration::zero()
+    }
+
+    /// The end of the entry, now for a running timer.
+    pub fn end_or_now(&self) -> DateTime<Utc> {
+        match self.end {
+            Some(end) if !self.is_running() => end,
+            _ => Utc::now(),
+        }
+    }
+
+    /// The tracked duration, the elapsed time for a running timer.
+    pub fn elapsed(&self) -> Duration {
+        if self.is_running() {
+            Utc::now() - self.start
+        } else {
+            self.duration
+        }
+    }
+
+    /// The part of the tracked duration that falls into `range`. An entry reaching over the
+    /// range's edges counts in proportion to how much of its span lies inside.
+    pub fn elapsed_in(&self, range: &Range) -> Duration {
+        let (start, end) = (self.start, self.end_or_now());
+        if end <= start {
+            // no span to split, the entry belongs where it starts
+            return if range.contains(start) { self.elapsed() } else { Duration::zero() };
+        }
+        let inside = end.min(range.end) - start.max(range.start);
+        if inside <= Duration::zero() {
+            return Duration::zero();
+        }
+        let span = end - start;
+        if inside == span {
+            return self.elapsed();
+        }
+        Duration::milliseconds((self.elapsed().num_milliseconds() as f64 * inside.num_milliseconds() as f64 / span.num_milliseconds() as f64) as i64)
+    }
+}
+
+/// A time entry tag.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tag_fg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tag_bg: Option<String>,
+}
+
+impl Tag {
+    /// A tag referenced by name only, ClickUp keeps the colors of existing tags.
+    pub fn named(name: &str) -> Self {
+        Tag {
+            name: name.to_string(),
+            tag_fg: None,
+            tag_bg: None,
+        }
+    }
+}
+
+/// Response of `GET /team/{team_id}/time_entries/tags`.
+#[derive(Debug, Deserialize)]
+pub struct Tags {
+    pub data: Vec<Tag>,
+}
+
+/// A task, either as returned by the task endpoints or embedded in a time entry.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Task {
+    pub id: String,
+    #[serde(default)]
+    pub custom_id: Option<String>,
+    pub name: String,
+    pub status: Status,
+    #[serde(default)]
+    pub text_content: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(with = "ms::opt_timestamp", default)]
+    pub date_created: Option<DateTime<Utc>>,
+    #[serde(with = "ms::opt_timestamp", default)]
+    pub date_updated: Option<DateTime<Utc>>,
+    #[serde(with = "ms::opt_timestamp", default)]
+    pub date_closed: Option<DateTime<Utc>>,
+    #[serde(with = "ms::opt_timestamp", default)]
+    pub due_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub creator: Option<Member>,
+    #[serde(default)]
+    pub assignees: Vec<Member>,
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub list: Option<ListRef>,
+    #[serde(default)]
+    pub folder: Option<FolderRef>,
+    #[serde(default)]
+    pub space: Option<SpaceRef>,
+}
+
+/// A task status as configured on a list.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Status {
+    pub status: String,
+    #[serde(default)]
+    pub color: Option<String>,
+    /// "open", "custom", "done" or "closed"
+    #[serde(rename = "type", default)]
+    pub kind: Option<String>,
+}
+
+/// A list as referenced from tasks, folders and spaces.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ListRef {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// A folder as referenced from tasks.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct FolderRef {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// A space as referenced from tasks.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SpaceRef {
+    pub id: String,
+}
+
+/// One page of `GET /team/{team_id}/task` or `GET /list/{list_id}/task`.
+#[derive(Debug, Deserialize)]
+pub struct Tasks {
+    pub tasks: Vec<Task>,
+    #[serde(default)]
+    pub last_page: Option<bool>,
+}
+
+/// A comment on a task.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Comment {
+    pub id: String,
+    #[serde(default)]
+    pub comment_text: String,
+    pub user: Member,
+    #[serde(with = "ms::timestamp")]
+    pub date: DateTime<Utc>,
+}
+
+/// One page of `GET /task/{task_id}/comment`.
+#[derive(Debug, Deserialize)]
+pub struct Comments {
+    pub comments: Vec<Comment>,
+}
+
+/// Response of `GET /list/{list_id}`.
+#[derive(Debug, Deserialize)]
+pub struct List {
+    pub id: String,
+    pub name: String,
+    pub statuses: Vec<Status>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Lists {
+    pub lists: Vec<ListRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Space {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Spaces {
+    pub spaces: Vec<Space>,
+}
+
+/// A folder with the lists it contains.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Folder {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub lists: Vec<ListRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Folders {
+    pub folders: Vec<Folder>,
+}
+
+/// A space with its folders and folderless lists.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SpaceNode {
+    pub id: String,
+    pub name: String,
+    pub folders: Vec<Folder>,
+    pub lists: Vec<ListRef>,
+}
+
+/// The space/folder/list tree of a team.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Hierarchy {
+    pub spaces: Vec<SpaceNode>,
+}
+
+/// A user, as a team member, assignee or author.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Member {
+    pub id: u64,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub initials: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TeamMember {
+    pub user: Member,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Team {
+    pub id: String,
+    #[serde(default)]
+    pub members: Vec<TeamMember>,
+}
+
+/// Response of `GET /team`, the teams (workspaces) the token has access to.
+#[derive(Debug, Deserialize)]
+pub struct Teams {
+    pub teams: Vec<Team>,
+}
+
+/// Body of `POST /team/{team_id}/time_entries`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct NewTimeEntry {
+    #[serde(with = "ms::timestamp")]
+    pub start: DateTime<Utc>,
+    #[serde(with = "ms::duration")]
+    pub duration: Duration,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub tags: Vec<Tag>,
+    /// None leaves the decision to ClickUp's workspace default
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub billable: Option<bool>,
+}
+
+impl NewTimeEntry {
+    pub fn end(&self) -> DateTime<Utc> {
+        self.start + self.duration
+    }
+}
+
+/// Body of `PUT /team/{team_id}/time_entries/{timer_id}` moving an entry; ClickUp wants start and end together.
+#[derive(Debug, Serialize)]
+pub struct TimeEntryUpdate {
+    #[serde(with = "ms::timestamp")]
+    pub start: DateTime<Utc>,
+    #[serde(with = "ms::timestamp")]
+    pub end: DateTime<Utc>,
+    #[serde(with = "ms::duration")]
+    pub duration: Duration,
+}
+
+/// Body of `POST /team/{team_id}/time_entries/start`.
+#[derive(Debug, Serialize, Default)]
+pub struct StartTimer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<Tag>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub billable: Option<bool>,
+}
+
+/// Body of `POST` and `DELETE /team/{team_id}/time_entries/tags`, adding tags to or removing
+/// them from time entries. ClickUp has no standalone tags, a tag exists while an entry carries it.
+#[derive(Debug, Serialize)]
+pub struct TimeEntryTags {
+    pub time_entry_ids: Vec<String>,
+    pub tags: Vec<Tag>,
+}
+
+/// Body of `PUT /team/{team_id}/time_entries/tags`.
+#[derive(Debug, Serialize)]
+pub struct TagRename {
+    pub name: String,
+    pub new_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag_fg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag_bg: Option<String>,
+}
+
+/// Body of `PUT /task/{task_id}`, only the fields cupcli changes.
+#[derive(Debug, Serialize)]
+pub struct TaskUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+/// Body of `POST /task/{task_id}/comment`.
+#[derive(Debug, Serialize)]
+pub struct NewComment {
+    pub comment_text: String,
+    pub notify_all: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Range, Zone};
+    use chrono::NaiveDate;
+
+    // ClickUp sends timestamps and durations as strings of milliseconds
+    fn entry(start: &str, end: &str, duration_mins: i64) -> TimeEntry {
+        let ms = |t: &str| t.parse::<DateTime<Utc>>().unwrap().timestamp_millis().to_string();
+        let raw = serde_json::json!({
+            "id": "1",
+            "start": ms(start),
+            "end": ms(end),
+            "duration": (duration_mins * 60 * 1000).to_string(),
+        });
+        serde_json::from_value(raw).unwrap()
+    }
+
+    fn day(d: u32) -> Range {
+        Range::day(Zone::parse("UTC").unwrap(), NaiveDate::from_ymd_opt(2024, 5, d).unwrap())
+    }
+
+    #[test]
+    fn entries_crossing_midnight_are_split() {
+        let night = entry("2024-05-01T22:00:00Z", "2024-05-02T02:00:00Z", 240);
+        assert_eq!(night.elapsed_in(&day(1)), Duration::hours(2));
+        assert_eq!(night.elapsed_in(&day(2)), Duration::hours(2));
+        assert_eq!(night.elapsed_in(&day(3)), Duration::zero());
+    }
+
+    #[test]
+    fn paused_entries_split_in_proportion() {
+        // an hour of pauses within the span, three quarters of it lie on the first day
+        let paused = entry("2024-05-01T21:00:00Z", "2024-05-02T01:00:00Z", 180);
+        assert_eq!(paused.elapsed_in(&day(1)), Duration::minutes(135));
+        assert_eq!(paused.elapsed_in(&day(2)), Duration::minutes(45));
+    }
+
+    #[test]
+    fn entries_inside_the_range_count_fully() {
+        let morning = entry("2024-05-01T08:00:00Z", "2024-05-01T12:00:00Z", 240);
+        assert_eq!(morning.elapsed_in(&day(1)), Duration::hours(4));
+        assert_eq!(hours(morning.elapsed_in(&day(1))), 4.0);
+    }
+
+    #[test]
+    fn entries_without_a_span_belong_to_their_start() {
+        // booked with a duration only, start and end are the same instant
+        let booked = entry("2024-05-01T23:30:00Z", "2024-05-01T23:30:00Z", 90);
+        assert_eq!(booked.elapsed_in(&day(1)), Duration::minutes(90));
+        assert_eq!(booked.elapsed_in(&day(2)), Duration::zero());
+    }
+}
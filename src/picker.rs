@@ -0,0 +1,103 @@
+use std::io::{self, IsTerminal};
+
+use chrono::Days;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, TerminalOptions, Viewport};
+
+use crate::api::recent_tasks;
+use crate::display::ERROR;
+use cupcli::cache;
+use cupcli::clock::Range;
+use cupcli::search::fuzzy;
+use cupcli::{Cfg, Client, Error, Task};
+
+// lines of the terminal the picker takes below the prompt, the query line included
+const HEIGHT: u16 = 12;
+// how far back tracked tasks count as recent
+const RECENT_DAYS: u64 = 14;
+const RECENT_LIMIT: usize = 20;
+
+// recently tracked tasks first, then the open ones assigned to the user in the sprint list
+fn candidates(cfg: &Cfg) -> Result<Vec<Task>, Error> {
+    let today = cfg.zone.today();
+    let range = Range::days(cfg.zone, today - Days::new(RECENT_DAYS), today);
+    let mut tasks = recent_tasks(&Client::new(cfg).time_entries_overlapping(&range)?, RECENT_LIMIT);
+    let assignee = Some(cfg.user_id.as_str()).filter(|id| !id.is_empty());
+    for task in cache::list_tasks(cfg, &cfg.list_id, assignee)? {
+        if !tasks.iter().any(|t| t.id == task.id) {
+            tasks.push(task);
+        }
+    }
+    Ok(tasks)
+}
+
+fn label(task: &Task) -> String {
+    format!("{:<12} {}", task.custom_id.as_deref().unwrap_or(&task.id), task.name)
+}
+
+fn run(terminal: &mut DefaultTerminal, tasks: &[Task]) -> io::Result<Option<Task>> {
+    let mut query = String::new();
+    let mut state = ListState::default().with_selected(Some(0));
+    loop {
+        let matches = fuzzy(tasks, &query);
+        if state.selected().is_none_or(|i| i >= matches.len()) {
+            state.select(Some(matches.len().saturating_sub(1)));
+        }
+        terminal.draw(|frame| {
+            let [prompt, list] = Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(frame.area());
+            let counter = format!("  {}/{}", matches.len(), tasks.len());
+            let line = Line::from(vec!["task> ".bold(), query.as_str().into(), counter.dark_gray()]);
+            frame.render_widget(Paragraph::new(line), prompt);
+            let items: Vec<ListItem> = matches.iter().map(|task| ListItem::new(label(task))).collect();
+            let highlight = Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD);
+            frame.render_stateful_widget(List::new(items).highlight_style(highlight), list, &mut state);
+        })?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            KeyCode::Enter => return Ok(state.selected().and_then(|i| matches.get(i)).map(|task| (*task).clone())),
+            KeyCode::Up => state.select_previous(),
+            KeyCode::Down => state.select_next(),
+            KeyCode::Backspace => {
+                query.pop();
+                state.select(Some(0));
+            }
+            KeyCode::Char(c) => {
+                query.push(c);
+                state.select(Some(0));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Lets the user fuzzy find one of their recent or assigned tasks, None if they cancelled.
+/// Fails instead of prompting when stdout is no terminal, e.g. in scripts and the daemon.
+pub fn pick_task(cfg: &Cfg) -> Result<Option<Task>, String> {
+    if !io::stdout().is_terminal() {
+        return Err(format!("{} No task id given and stdout is not a terminal to pick one, pass the task id", ERROR));
+    }
+    let tasks = candidates(cfg).map_err(|e| format!("[REQUEST ERROR] {}", e))?;
+    if tasks.is_empty() {
+        return Err(format!("{} No recent or assigned tasks to pick from, pass the task id", ERROR));
+    }
+    let mut terminal = ratatui::try_init_with_options(TerminalOptions { viewport: Viewport::Inline(HEIGHT) }).map_err(|e| {
+        ratatui::restore();
+        format!("{} {}", ERROR, e)
+    })?;
+    let picked = run(&mut terminal, &tasks);
+    // leave the prompt line the picker started on as it was
+    let _ = terminal.clear();
+    ratatui::restore();
+    picked.map_err(|e| format!("{} {}", ERROR, e))
+}
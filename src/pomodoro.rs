@@ -0,0 +1,209 @@
+//! Pomodoro cycles on top of the ClickUp timer: the timer runs during work blocks only.
+//!
+//! While a pomodoro runs its current phase is kept in `$XDG_DATA_HOME/cupcli/pomodoro.json`,
+//! so a crashed or interrupted run can be resumed.
+
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::data_dir;
+
+/// Tag of the time entries of work blocks.
+pub const TAG: &str = "pomodoro";
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum Phase {
+    Work,
+    Break,
+    LongBreak,
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Work => "Work",
+            Phase::Break => "Break",
+            Phase::LongBreak => "Long break",
+        }
+    }
+}
+
+/// Lengths in minutes, the `pomodoro` cfg key as `work/break/long break/every`: a long break
+/// follows every `long_break_every`th work block. A run has `rounds` work blocks.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct Plan {
+    pub work_mins: i64,
+    pub break_mins: i64,
+    pub long_break_mins: i64,
+    pub long_break_every: u32,
+    pub rounds: u32,
+}
+
+impl Default for Plan {
+    fn default() -> Self {
+        Plan {
+            work_mins: 25,
+            break_mins: 5,
+            long_break_mins: 15,
+            long_break_every: 4,
+            rounds: 4,
+        }
+    }
+}
+
+impl Plan {
+    /// Parses `25/5/15/4`; a run is one set of blocks up to the long break.
+    pub fn parse(value: &str) -> Result<Plan, String> {
+        let parts = value
+            .split('/')
+            .map(|part| part.trim().parse::<i64>().ok().filter(|n| *n > 0).ok_or(format!("invalid minutes `{}`", part.trim())))
+            .collect::<Result<Vec<i64>, String>>()?;
+        let [work_mins, break_mins, long_break_mins, every] = parts[..] else {
+            return Err(format!("expected `work/break/long break/every`, got `{}`", value));
+        };
+        Ok(Plan {
+            work_mins,
+            break_mins,
+            long_break_mins,
+            long_break_every: every as u32,
+            rounds: every as u32,
+        })
+    }
+
+    fn length(&self, phase: Phase) -> Duration {
+        Duration::minutes(match phase {
+            Phase::Work => self.work_mins,
+            Phase::Break => self.break_mins,
+            Phase::LongBreak => self.long_break_mins,
+        })
+    }
+}
+
+/// The current phase of a running pomodoro.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Pomodoro {
+    pub task_id: String,
+    pub task_name: String,
+    pub plan: Plan,
+    /// the current or last work block, counting from 1
+    pub round: u32,
+    pub phase: Phase,
+    pub phase_end: DateTime<Utc>,
+    /// the timer entry of the current work block, once it was started
+    #[serde(default)]
+    pub entry_id: Option<String>,
+}
+
+impl Pomodoro {
+    /// The first work block, starting at `start`.
+    pub fn new(task_id: String, task_name: String, plan: Plan, start: DateTime<Utc>) -> Pomodoro {
+        Pomodoro {
+            task_id,
+            task_name,
+            plan,
+            round: 1,
+            phase: Phase::Work,
+            phase_end: start + plan.length(Phase::Work),
+            entry_id: None,
+        }
+    }
+
+    /// The phase after this one, starting at `start`; None once the last work block is over.
+    pub fn next(&self, start: DateTime<Utc>) -> Option<Pomodoro> {
+        let (round, phase) = match self.phase {
+            Phase::Work if self.round >= self.plan.rounds => return None,
+            Phase::Work if self.round.is_multiple_of(self.plan.long_break_every.max(1)) => (self.round, Phase::LongBreak),
+            Phase::Work => (self.round, Phase::Break),
+            Phase::Break | Phase::LongBreak => (self.round + 1, Phase::Work),
+        };
+        Some(Pomodoro {
+            round,
+            phase,
+            phase_end: start + self.plan.length(phase),
+            entry_id: None,
+            ..self.clone()
+        })
+    }
+}
+
+fn state_path() -> Option<PathBuf> {
+    Some(data_dir()?.join("pomodoro.json"))
+}
+
+/// The pomodoro left behind by the last run, None if it finished or was stopped.
+pub fn load() -> Option<Pomodoro> {
+    let raw = fs::read_to_string(state_path()?).ok()?;
+    serde_json::from_str(&raw).ok()
+}
+
+pub fn save(pomodoro: &Pomodoro) -> std::io::Result<()> {
+    let path = state_path().ok_or(std::io::ErrorKind::NotFound)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string(pomodoro)?)
+}
+
+pub fn clear() {
+    if let Some(path) = state_path() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start() -> DateTime<Utc> {
+        "2024-05-01T09:00:00Z".parse().unwrap()
+    }
+
+    fn phases(plan: Plan) -> Vec<(u32, Phase)> {
+        let mut pomodoro = Pomodoro::new("t1".to_string(), "Task".to_string(), plan, start());
+        let mut phases = vec![(pomodoro.round, pomodoro.phase)];
+        while let Some(next) = pomodoro.next(pomodoro.phase_end) {
+            phases.push((next.round, next.phase));
+            pomodoro = next;
+        }
+        phases
+    }
+
+    #[test]
+    fn a_run_ends_after_its_last_work_block() {
+        use Phase::*;
+        assert_eq!(phases(Plan::default()), [(1, Work), (1, Break), (2, Work), (2, Break), (3, Work), (3, Break), (4, Work)]);
+    }
+
+    #[test]
+    fn long_breaks_every_nth_block() {
+        use Phase::*;
+        let plan = Plan { rounds: 5, long_break_every: 2, ..Plan::default() };
+        assert_eq!(
+            phases(plan),
+            [(1, Work), (1, Break), (2, Work), (2, LongBreak), (3, Work), (3, Break), (4, Work), (4, LongBreak), (5, Work)]
+        );
+    }
+
+    #[test]
+    fn phases_end_after_their_length() {
+        let plan = Plan { rounds: 3, ..Plan::parse("50/10/30/2").unwrap() };
+        let work = Pomodoro::new("t1".to_string(), "Task".to_string(), plan, start());
+        assert_eq!(work.phase_end - start(), Duration::minutes(50));
+        let pause = work.next(work.phase_end).unwrap();
+        assert_eq!((pause.phase, pause.phase_end - work.phase_end), (Phase::Break, Duration::minutes(10)));
+        let long = pause.next(pause.phase_end).unwrap().next(start()).unwrap();
+        assert_eq!((long.phase, long.phase_end - start()), (Phase::LongBreak, Duration::minutes(30)));
+    }
+
+    #[test]
+    fn plans_from_the_cfg() {
+        let plan = Plan::parse(" 25 / 5 / 15 / 4 ").unwrap();
+        assert_eq!((plan.work_mins, plan.break_mins, plan.long_break_mins, plan.long_break_every, plan.rounds), (25, 5, 15, 4, 4));
+        assert!(Plan::parse("25/5/15").is_err());
+        assert!(Plan::parse("25/0/15/4").is_err());
+        assert!(Plan::parse("25/five/15/4").is_err());
+    }
+}
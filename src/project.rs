@@ -0,0 +1,126 @@
+//! Per-repository settings from a `.cupcli.toml`, found walking up from the working directory.
+//!
+//! The top-level keys replace the ones of the global cfg for commands run inside the project:
+//!
+//! ```toml
+//! list_id = "901234567"
+//! folder_id = "90123456"
+//! space_id = "9012345"
+//! default_tags = ["frontend"]
+//! default_billable = true
+//! branch_pattern = "PROJ-[0-9]+"
+//!
+//! [git]
+//! link_commits = true      # append the branch's task id to commit messages
+//! comment_commits = true   # comment each commit on the task
+//! book_commits = true      # book the time since the previous commit on the task
+//! max_book_minutes = 120
+//! ```
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::config::Cfg;
+
+const FILE_NAME: &str = ".cupcli.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Project {
+    pub list_id: Option<String>,
+    pub folder_id: Option<String>,
+    pub space_id: Option<String>,
+    pub default_tags: Option<Vec<String>>,
+    pub default_billable: Option<bool>,
+    pub branch_pattern: Option<String>,
+    pub git: GitHooks,
+}
+
+impl Project {
+    /// Replaces the settings of `cfg` that the project sets and returns why invalid ones were
+    /// left as they were.
+    pub fn apply(&self, cfg: &mut Cfg) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(pattern) = &self.branch_pattern {
+            match Regex::new(pattern) {
+                Ok(pattern) => cfg.branch_pattern = pattern,
+                Err(e) => errors.push(format!("invalid branch_pattern: {}", e)),
+            }
+        }
+        if let Some(list_id) = &self.list_id {
+            cfg.list_id = list_id.clone();
+        }
+        if let Some(folder_id) = &self.folder_id {
+            cfg.folder_id = folder_id.clone();
+        }
+        if let Some(space_id) = &self.space_id {
+            cfg.space_id = space_id.clone();
+        }
+        if let Some(tags) = &self.default_tags {
+            cfg.default_tags = tags.clone();
+        }
+        if self.default_billable.is_some() {
+            cfg.default_billable = self.default_billable;
+        }
+        errors
+    }
+}
+
+/// What the hooks of `git install-hooks` do, everything is off unless a project opts in.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitHooks {
+    pub link_commits: bool,
+    pub comment_commits: bool,
+    pub book_commits: bool,
+    /// longer times between two commits are booked as this many minutes
+    pub max_book_minutes: i64,
+}
+
+impl Default for GitHooks {
+    fn default() -> Self {
+        GitHooks {
+            link_commits: false,
+            comment_commits: false,
+            book_commits: false,
+            max_book_minutes: 120,
+        }
+    }
+}
+
+/// The closest `.cupcli.toml` in the working directory or above it.
+pub fn find() -> Option<PathBuf> {
+    let cwd = env::current_dir().ok()?;
+    cwd.ancestors().map(|dir| dir.join(FILE_NAME)).find(|path| path.is_file())
+}
+
+/// The project of the working directory, None outside of one.
+pub fn load() -> Result<Option<(PathBuf, Project)>, String> {
+    let Some(path) = find() else {
+        return Ok(None);
+    };
+    let raw = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let project = toml::from_str(&raw).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(Some((path, project)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_settings_leave_the_others_applied() {
+        let project: Project = toml::from_str("list_id = \"901\"\nbranch_pattern = \"(\"\ndefault_tags = [\"frontend\"]\n").unwrap();
+        let mut cfg: Cfg = [("branch_pattern", "PROJ-[0-9]+")].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let errors = project.apply(&mut cfg);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("invalid branch_pattern"));
+        assert_eq!(cfg.list_id, "901");
+        assert_eq!(cfg.default_tags, ["frontend"]);
+        assert_eq!(cfg.branch_pattern.as_str(), "PROJ-[0-9]+");
+    }
+}
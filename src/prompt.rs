@@ -0,0 +1,21 @@
+use std::io::{self, BufRead, Write};
+
+// prints the question and reads a single trimmed line from stdin; None on EOF or read errors
+pub fn ask(question: &str) -> Option<String> {
+    print!("{} ", question);
+    io::stdout().flush().ok()?;
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_string()),
+    }
+}
+
+// asks for a 1-based index into a list of `len` items and returns the 0-based index
+pub fn ask_index(question: &str, len: usize) -> Option<usize> {
+    let answer = ask(question)?;
+    match answer.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= len => Some(n - 1),
+        _ => None,
+    }
+}
@@ -0,0 +1,67 @@
+//! Reminders to track time: nothing running during the working hours for too long.
+//!
+//! Notifications go through the freedesktop notification D-Bus interface via `gdbus`, or through
+//! a hook command like `remind_hook` if one is configured.
+
+use std::process::Command;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::config::Cfg;
+use crate::models::TimeEntry;
+
+/// Since when nothing has been tracked if a reminder is due at `now`, None if a timer is
+/// running, it's outside the working hours or the last entry ended less than `remind_after`
+/// minutes ago. `entries` are the entries of today.
+pub fn untracked_since(cfg: &Cfg, now: DateTime<Utc>, timer: Option<&TimeEntry>, entries: &[TimeEntry]) -> Option<DateTime<Utc>> {
+    if timer.is_some_and(TimeEntry::is_running) || entries.iter().any(TimeEntry::is_running) {
+        return None;
+    }
+    let today = cfg.zone.date_of(now);
+    let (start, end) = (cfg.zone.at(today, cfg.work_start), cfg.zone.at(today, cfg.work_end));
+    if cfg.quota(today) <= 0.0 || now < start || now >= end {
+        return None;
+    }
+    // time tracked before the working hours started doesn't count as tracked within them
+    let since = entries.iter().map(TimeEntry::end_or_now).filter(|end| *end <= now).fold(start, DateTime::max);
+    Some(since).filter(|since| now - *since >= Duration::minutes(cfg.remind_after_mins))
+}
+
+/// Shows a desktop notification, or runs the `hook` command with the summary and the body in
+/// `$CUPCLI_SUMMARY` and `$CUPCLI_BODY`.
+pub fn notify(hook: Option<&str>, summary: &str, body: &str) -> Result<(), String> {
+    let mut command = match hook {
+        Some(hook) => {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(hook).env("CUPCLI_SUMMARY", summary).env("CUPCLI_BODY", body);
+            command
+        }
+        None => {
+            // org.freedesktop.Notifications.Notify(app_name, replaces_id, app_icon, summary, body, actions, hints, expire_timeout)
+            let mut command = Command::new("gdbus");
+            command
+                .args(["call", "--session", "--dest", "org.freedesktop.Notifications"])
+                .args(["--object-path", "/org/freedesktop/Notifications"])
+                .args(["--method", "org.freedesktop.Notifications.Notify"])
+                .args(["cupcli", "0", "", summary, body, "[]", "{}", "-1"]);
+            command
+        }
+    };
+    let output = command.output().map_err(|e| format!("could not run {:?}: {}", command.get_program(), e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!("{:?} failed: {}", command.get_program(), String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+/// Notifies if a reminder is due now, see [`untracked_since`]. Returns since when nothing has
+/// been tracked if it notified.
+pub fn remind(cfg: &Cfg, timer: Option<&TimeEntry>, entries: &[TimeEntry]) -> Result<Option<DateTime<Utc>>, String> {
+    let Some(since) = untracked_since(cfg, Utc::now(), timer, entries) else {
+        return Ok(None);
+    };
+    let body = format!("Nothing tracked since {}, start a timer with `cupcli timer start`", cfg.zone.naive(since).format("%H:%M"));
+    notify(cfg.remind_hook.as_deref(), "No timer running", &body)?;
+    Ok(Some(since))
+}
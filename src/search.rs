@@ -0,0 +1,153 @@
+//! Ranking of tasks for `task search` and the fuzzy task picker.
+
+use chrono::{DateTime, Utc};
+
+use crate::models::Task;
+
+// relevance of a task for the query; None if any query term matches neither name nor (optionally) description
+fn relevance(task: &Task, query: &str, terms: &[String], with_description: bool) -> Option<f32> {
+    let name = task.name.to_lowercase();
+    let description = task.text_content.as_deref().unwrap_or_default().to_lowercase();
+    let mut score = 0f32;
+    if name == query {
+        score += 100f32;
+    } else if name.starts_with(query) {
+        score += 60f32;
+    } else if name.contains(query) {
+        score += 40f32;
+    }
+    for term in terms {
+        if name.split(|c: char| !c.is_alphanumeric()).any(|word| word.starts_with(term.as_str())) {
+            score += 20f32;
+        } else if name.contains(term.as_str()) {
+            score += 10f32;
+        } else if with_description && description.contains(term.as_str()) {
+            score += 3f32;
+        } else {
+            return None;
+        }
+    }
+    Some(score)
+}
+
+// bonus between 0 and 10 that halves roughly every week since the last update
+fn recency(task: &Task, now: DateTime<Utc>) -> f32 {
+    match task.date_updated {
+        Some(updated) => {
+            let days = (now - updated).num_minutes().max(0) as f32 / 60f32 / 24f32;
+            10f32 / (1f32 + days / 7f32)
+        }
+        None => 0f32,
+    }
+}
+
+/// Ranks tasks matching all terms of `query` by relevance first and recency second, best match first.
+pub fn rank<'a>(tasks: &'a [Task], query: &str, with_description: bool) -> Vec<&'a Task> {
+    let query = query.trim().to_lowercase();
+    let terms: Vec<String> = query.split_whitespace().map(|t| t.to_string()).collect();
+    let now = Utc::now();
+    let mut scored: Vec<(f32, &Task)> = tasks
+        .iter()
+        .filter_map(|task| {
+            relevance(task, &query, &terms, with_description).map(|score| (score + recency(task, now), task))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().map(|(_, task)| task).collect()
+}
+
+// fzf-like score of `query` as a subsequence of `text`: consecutive characters and word starts
+// score higher, shorter texts are closer matches; None if the characters don't all appear in order
+fn subsequence_score(text: &str, query: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut from = 0;
+    let mut last: Option<usize> = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = (from..text.len()).find(|&i| text[i] == c)?;
+        score += 10;
+        if last.is_some_and(|last| last + 1 == i) {
+            score += 15;
+        }
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += 10;
+        }
+        last = Some(i);
+        from = i + 1;
+    }
+    Some(score * 100 - text.len() as i32)
+}
+
+/// Tasks whose name or id contain the characters of `query` in order, best match first. Ties
+/// and an empty query keep the order of `tasks`.
+pub fn fuzzy<'a>(tasks: &'a [Task], query: &str) -> Vec<&'a Task> {
+    // an empty query matches every text alike, only their lengths would tell them apart
+    if query.trim().is_empty() {
+        return tasks.iter().collect();
+    }
+    let mut scored: Vec<(i32, &Task)> = tasks
+        .iter()
+        .filter_map(|task| {
+            let ids = [Some(task.id.as_str()), task.custom_id.as_deref()];
+            let score = ids.into_iter().flatten().chain([task.name.as_str()]).filter_map(|text| subsequence_score(text, query)).max()?;
+            Some((score, task))
+        })
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, task)| task).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Days;
+
+    fn task(id: &str, name: &str) -> Task {
+        serde_json::from_value(serde_json::json!({"id": id, "name": name, "status": {"status": "open"}})).unwrap()
+    }
+
+    fn names(tasks: Vec<&Task>) -> Vec<&str> {
+        tasks.into_iter().map(|task| task.name.as_str()).collect()
+    }
+
+    #[test]
+    fn exact_names_before_prefixes_before_contained() {
+        let tasks = [task("1", "Fix login"), task("2", "Login page"), task("3", "Login")];
+        assert_eq!(names(rank(&tasks, "login", false)), ["Login", "Login page", "Fix login"]);
+    }
+
+    #[test]
+    fn every_term_has_to_match() {
+        let mut tasks = [task("1", "Fix login"), task("2", "Login bug")];
+        tasks[0].text_content = Some("The bug in the form".to_string());
+        assert_eq!(names(rank(&tasks, "login bug", false)), ["Login bug"]);
+        assert_eq!(names(rank(&tasks, "login bug", true)), ["Login bug", "Fix login"]);
+        assert!(rank(&tasks, "logout", true).is_empty());
+    }
+
+    #[test]
+    fn recently_updated_tasks_win_ties() {
+        let mut tasks = [task("1", "Deploy"), task("2", "Deploy")];
+        tasks[0].date_updated = Some(Utc::now() - Days::new(30));
+        tasks[1].date_updated = Some(Utc::now() - Days::new(1));
+        let ranked = rank(&tasks, "deploy", false);
+        assert_eq!(ranked.iter().map(|task| task.id.as_str()).collect::<Vec<&str>>(), ["2", "1"]);
+    }
+
+    #[test]
+    fn fuzzy_prefers_word_starts_and_runs() {
+        let tasks = [task("1", "Fix catalog"), task("2", "Login"), task("3", "Release")];
+        assert_eq!(names(fuzzy(&tasks, "log")), ["Login", "Fix catalog"]);
+        assert_eq!(names(fuzzy(&tasks, "lgn")), ["Login"]);
+        assert!(fuzzy(&tasks, "xyz").is_empty());
+    }
+
+    #[test]
+    fn fuzzy_matches_ids_and_keeps_the_order_without_query() {
+        let mut tasks = [task("86abc123", "Release"), task("86def456", "Login")];
+        tasks[1].custom_id = Some("DEV-42".to_string());
+        assert_eq!(names(fuzzy(&tasks, "dev42")), ["Login"]);
+        assert_eq!(names(fuzzy(&tasks, "86abc")), ["Release"]);
+        assert_eq!(names(fuzzy(&tasks, "")), ["Release", "Login"]);
+    }
+}
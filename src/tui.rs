@@ -0,0 +1,350 @@
+use std::io::{self, IsTerminal};
+use std::time::{Duration, Instant};
+
+use chrono::{Days, NaiveDate};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::api::{book_gaps, task_create_comment, task_status, timer_start, timer_stop};
+use crate::display::{fmt_time, ERROR, HOURGLASS, STOPWATCH};
+use cupcli::cache::{self, CacheMode};
+use cupcli::check::{gaps_between, Issue};
+use cupcli::clock::Range;
+use cupcli::models::hours;
+use cupcli::utils::calculate_time_in;
+use cupcli::{Cfg, Client, Error, Status, Task, TimeEntry};
+
+// the panes are reloaded from the cache this often, the cache fetches once its TTL ran out
+const RELOAD_EVERY: Duration = Duration::from_secs(30);
+// redraws at least this often so the running timer ticks
+const TICK: Duration = Duration::from_secs(1);
+
+const HELP: &str = "tab pane · ↑↓ select · ←→ day · t today · s start · x stop · u status · c comment · b book gaps · r refresh · q quit";
+
+#[derive(PartialEq)]
+enum Pane {
+    Entries,
+    Tasks,
+}
+
+enum Mode {
+    Normal,
+    Status { task: Task, statuses: Vec<Status>, state: ListState },
+    Comment { task: Task, text: String },
+    ConfirmGaps { task: Task, count: usize, total: f32 },
+}
+
+struct App<'a> {
+    cfg: &'a Cfg,
+    day: NaiveDate,
+    entries: Vec<TimeEntry>,
+    tasks: Vec<Task>,
+    timer: Option<TimeEntry>,
+    pane: Pane,
+    entries_state: ListState,
+    tasks_state: ListState,
+    mode: Mode,
+    message: String,
+    loaded_at: Instant,
+    quit: bool,
+}
+
+// the first line of what a command returned, the status line has room for one
+fn first_line(res: Result<String, Error>) -> String {
+    match res {
+        Ok(out) => out.lines().next().unwrap_or_default().to_string(),
+        Err(e) => format!("{} {}", ERROR, e),
+    }
+}
+
+fn select_within(state: &mut ListState, len: usize) {
+    match state.selected() {
+        _ if len == 0 => state.select(None),
+        Some(i) if i >= len => state.select(Some(len - 1)),
+        None => state.select(Some(0)),
+        _ => {}
+    }
+}
+
+impl<'a> App<'a> {
+    fn new(cfg: &'a Cfg) -> App<'a> {
+        App {
+            cfg,
+            day: cfg.zone.today(),
+            entries: Vec::new(),
+            tasks: Vec::new(),
+            timer: None,
+            pane: Pane::Tasks,
+            entries_state: ListState::default(),
+            tasks_state: ListState::default(),
+            mode: Mode::Normal,
+            message: HELP.to_string(),
+            loaded_at: Instant::now(),
+            quit: false,
+        }
+    }
+
+    // everything comes through the cache; `fresh` skips it, e.g. after changes made in the browser
+    fn load(&mut self, fresh: bool) {
+        let refreshing;
+        let cfg = if fresh {
+            refreshing = Cfg {
+                cache_mode: CacheMode::Refresh,
+                ..self.cfg.clone()
+            };
+            &refreshing
+        } else {
+            self.cfg
+        };
+        let assignee = Some(cfg.user_id.as_str()).filter(|id| !id.is_empty());
+        let loaded = cache::day_entries(cfg, self.day)
+            .and_then(|entries| Ok((entries, cache::current_timer(cfg)?, cache::list_tasks(cfg, &cfg.list_id, assignee)?)));
+        match loaded {
+            Ok((mut entries, timer, tasks)) => {
+                entries.sort_by_key(|entry| entry.start);
+                (self.entries, self.timer, self.tasks) = (entries, timer, tasks);
+            }
+            Err(e) => self.message = format!("{} {}", ERROR, e),
+        }
+        select_within(&mut self.entries_state, self.entries.len());
+        select_within(&mut self.tasks_state, self.tasks.len());
+        self.loaded_at = Instant::now();
+    }
+
+    fn selected_task(&self) -> Option<Task> {
+        match self.pane {
+            Pane::Tasks => self.tasks_state.selected().and_then(|i| self.tasks.get(i)).cloned(),
+            Pane::Entries => self.entries_state.selected().and_then(|i| self.entries.get(i)).and_then(|entry| entry.task.clone()),
+        }
+    }
+
+    fn show_day(&mut self, day: NaiveDate) {
+        self.day = day.min(self.cfg.zone.today());
+        self.entries_state.select(None);
+        self.load(false);
+    }
+
+    fn key(&mut self, key: KeyEvent) {
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.key_normal(key.code),
+            Mode::Status { task, statuses, mut state } => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    state.select_previous();
+                    self.mode = Mode::Status { task, statuses, state };
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    state.select_next();
+                    self.mode = Mode::Status { task, statuses, state };
+                }
+                KeyCode::Enter => {
+                    if let Some(status) = state.selected().and_then(|i| statuses.get(i)) {
+                        self.message = first_line(task_status(self.cfg, &task.id, &status.status));
+                        self.load(false);
+                    }
+                }
+                KeyCode::Esc | KeyCode::Char('q') => {}
+                _ => self.mode = Mode::Status { task, statuses, state },
+            },
+            Mode::Comment { task, mut text } => match key.code {
+                KeyCode::Enter if !text.trim().is_empty() => self.message = first_line(task_create_comment(self.cfg, &task.id, text.trim())),
+                KeyCode::Esc => {}
+                KeyCode::Backspace => {
+                    text.pop();
+                    self.mode = Mode::Comment { task, text };
+                }
+                KeyCode::Char(c) => {
+                    text.push(c);
+                    self.mode = Mode::Comment { task, text };
+                }
+                _ => self.mode = Mode::Comment { task, text },
+            },
+            Mode::ConfirmGaps { task, count, total } => match key.code {
+                KeyCode::Char('y') => {
+                    self.message = first_line(book_gaps(self.cfg, self.day, &task));
+                    self.load(false);
+                }
+                KeyCode::Char('n') | KeyCode::Esc | KeyCode::Char('q') => {}
+                _ => self.mode = Mode::ConfirmGaps { task, count, total },
+            },
+        }
+    }
+
+    fn key_normal(&mut self, code: KeyCode) {
+        let state = match self.pane {
+            Pane::Entries => &mut self.entries_state,
+            Pane::Tasks => &mut self.tasks_state,
+        };
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.pane = if self.pane == Pane::Tasks { Pane::Entries } else { Pane::Tasks };
+            }
+            KeyCode::Up | KeyCode::Char('k') => state.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => state.select_next(),
+            KeyCode::Left | KeyCode::Char('h') => self.show_day(self.day - Days::new(1)),
+            KeyCode::Right | KeyCode::Char('l') => self.show_day(self.day + Days::new(1)),
+            KeyCode::Char('t') => self.show_day(self.cfg.zone.today()),
+            KeyCode::Char('r') => {
+                self.load(true);
+                self.message = format!("{} Reloaded", HOURGLASS);
+            }
+            KeyCode::Char('x') => {
+                self.message = first_line(timer_stop(self.cfg));
+                self.load(false);
+            }
+            KeyCode::Char(action @ ('s' | 'u' | 'c' | 'b')) => {
+                let Some(task) = self.selected_task() else {
+                    self.message = "Select a task first".to_string();
+                    return;
+                };
+                match action {
+                    's' => {
+                        self.message = first_line(timer_start(self.cfg, Some(&task.id), Vec::new()));
+                        self.load(false);
+                    }
+                    'u' => self.pick_status(task),
+                    'c' => self.mode = Mode::Comment { task, text: String::new() },
+                    _ => self.confirm_gaps(task),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn pick_status(&mut self, task: Task) {
+        // tasks of time entries don't name their list, the full task does
+        let client = Client::new(self.cfg);
+        let statuses = client
+            .task(&task.id)
+            .map(|full| full.list.map_or(self.cfg.list_id.clone(), |list| list.id))
+            .and_then(|list_id| cache::list_statuses(self.cfg, &list_id));
+        match statuses {
+            Ok(statuses) => {
+                let current = statuses.iter().position(|status| status.status.eq_ignore_ascii_case(&task.status.status));
+                self.mode = Mode::Status { task, statuses, state: ListState::default().with_selected(current.or(Some(0))) };
+            }
+            Err(e) => self.message = format!("{} {}", ERROR, e),
+        }
+    }
+
+    fn confirm_gaps(&mut self, task: Task) {
+        let gaps = gaps_between(self.cfg, &self.entries, self.day, self.day);
+        let total: f32 = gaps
+            .iter()
+            .map(|gap| match gap {
+                Issue::Gap { start, end, .. } => hours(*end - *start),
+                _ => 0.0,
+            })
+            .sum();
+        if gaps.is_empty() {
+            self.message = format!("{} No gaps within the working hours to fill", HOURGLASS);
+        } else {
+            self.mode = Mode::ConfirmGaps { task, count: gaps.len(), total };
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [timer_area, main_area, message_area] = Layout::vertical([Constraint::Length(3), Constraint::Min(5), Constraint::Length(1)]).areas(frame.area());
+        let [entries_area, tasks_area] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(main_area);
+        let focused = |pane: Pane| if self.pane == pane { Style::default().fg(Color::Cyan) } else { Style::default() };
+        let highlight = Style::default().add_modifier(Modifier::REVERSED);
+
+        // today's tracked time includes a timer started after the entries were cached
+        let mut entries = self.entries.clone();
+        if let Some(timer) = self.timer.as_ref().filter(|timer| !entries.iter().any(|entry| entry.id == timer.id)) {
+            entries.push(timer.clone());
+        }
+        let tracked = calculate_time_in(&entries, &Range::day(self.cfg.zone, self.day));
+        let timer = match &self.timer {
+            Some(timer) => {
+                let elapsed = timer.elapsed();
+                let task = timer.task.as_ref().map_or("no task", |task| task.name.as_str());
+                format!("{} {}:{:02} {} since {}", STOPWATCH, elapsed.num_hours(), elapsed.num_minutes() % 60, task, self.cfg.zone.naive(timer.start).format("%H:%M"))
+            }
+            None => "No timer running".to_string(),
+        };
+        let day = format!("{} {} / {}", self.day.format("%a %d/%m/%Y"), fmt_time(tracked), fmt_time(self.cfg.quota(self.day)));
+        frame.render_widget(Paragraph::new(Line::from(timer)).block(Block::bordered().title(" Timer ").title_top(Line::from(format!(" {} ", day)).right_aligned())), timer_area);
+
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let (start, end) = (self.cfg.zone.naive(entry.start), self.cfg.zone.naive(entry.end_or_now()));
+                let task = entry.task.as_ref().map_or("no task", |task| task.name.as_str());
+                ListItem::new(format!("{}-{} {:>6} {}", start.format("%H:%M"), end.format("%H:%M"), fmt_time(hours(entry.elapsed())), task))
+            })
+            .collect();
+        let entries = List::new(items).block(Block::bordered().title(" Entries ").border_style(focused(Pane::Entries))).highlight_style(highlight);
+        frame.render_stateful_widget(entries, entries_area, &mut self.entries_state);
+
+        let items: Vec<ListItem> = self.tasks.iter().map(|task| ListItem::new(format!("{} [{}]", task.name, task.status.status))).collect();
+        let tasks = List::new(items).block(Block::bordered().title(" Sprint tasks ").border_style(focused(Pane::Tasks))).highlight_style(highlight);
+        frame.render_stateful_widget(tasks, tasks_area, &mut self.tasks_state);
+
+        frame.render_widget(Paragraph::new(self.message.as_str()), message_area);
+
+        match &mut self.mode {
+            Mode::Normal => {}
+            Mode::Status { task, statuses, state } => {
+                let area = popup(frame.area(), 40, statuses.len() as u16 + 2);
+                let items: Vec<ListItem> = statuses.iter().map(|status| ListItem::new(status.status.as_str())).collect();
+                frame.render_widget(Clear, area);
+                frame.render_stateful_widget(List::new(items).block(Block::bordered().title(format!(" Status of {} ", task.name))).highlight_style(highlight), area, state);
+            }
+            Mode::Comment { task, text } => {
+                let area = popup(frame.area(), 60, 3);
+                frame.render_widget(Clear, area);
+                frame.render_widget(Paragraph::new(format!("{}_", text)).block(Block::bordered().title(format!(" Comment on {} (enter sends, esc cancels) ", task.name))), area);
+            }
+            Mode::ConfirmGaps { task, count, total } => {
+                let area = popup(frame.area(), 60, 3);
+                frame.render_widget(Clear, area);
+                let question = format!("Book {} gaps, {} in total, on {}? [y/n]", count, fmt_time(*total), task.name);
+                frame.render_widget(Paragraph::new(question).block(Block::bordered().title(" Book gaps ")), area);
+            }
+        }
+    }
+}
+
+fn popup(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width.min(area.width))]).flex(Flex::Center).areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height.min(area.height))]).flex(Flex::Center).areas(area);
+    area
+}
+
+fn run(terminal: &mut DefaultTerminal, cfg: &Cfg) -> io::Result<()> {
+    let mut app = App::new(cfg);
+    app.load(false);
+    while !app.quit {
+        terminal.draw(|frame| app.draw(frame))?;
+        if event::poll(TICK)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.key(key);
+                }
+            }
+        }
+        if app.loaded_at.elapsed() >= RELOAD_EVERY {
+            app.load(false);
+        }
+    }
+    Ok(())
+}
+
+pub fn tui(cfg: &Cfg) -> String {
+    if !io::stdout().is_terminal() {
+        return format!("{} 'tui' needs a terminal", ERROR);
+    }
+    let mut terminal = ratatui::init();
+    let res = run(&mut terminal, cfg);
+    ratatui::restore();
+    match res {
+        Ok(()) => String::new(),
+        Err(e) => format!("{} {}", ERROR, e),
+    }
+}
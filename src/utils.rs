@@ -1,123 +1,269 @@
-use std::collections::HashMap;
-use serde::Deserialize;
+//! Helpers shared by the library and the CLI: time sums and the request layer.
 
-#[derive(Debug, Deserialize)]
-pub struct TimeEntries {
-    pub data: Vec<TimeEntry>,
-}
-
-#[allow(dead_code)]
-#[derive(Debug, Deserialize)]
-pub struct TimeEntry {
-    id: String,
-    pub task: Option<Task>,
-    start: String,
-    pub end: String,
-    duration: String,
-    task_url: Option<String>
-}
+use crate::models::*;
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct Task {
-    pub id: String,
-    pub name: String,
-    status: Status
+/// Sum of the entries' durations in hours.
+pub fn calculate_time(entries: &[TimeEntry]) -> f32 {
+    // calculate tracked time in hours, a running timer counts up to now
+    entries.iter().map(|entry| hours(entry.elapsed())).sum()
 }
 
-#[derive(Debug, Deserialize, Clone)]
-struct Status {
-    status: String
-}
-
-
-pub fn calculate_time(entries: TimeEntries) -> f32 {
-    // calculate tracked time in hours
-    entries.data.iter().map(|entry| {
-        entry.duration.parse::<f32>().unwrap() / 1000f32 / 60f32 / 60f32
-    }).sum()
+/// Sum of the entries' durations within `range` in hours, entries crossing its edges are clipped.
+pub fn calculate_time_in(entries: &[TimeEntry], range: &crate::clock::Range) -> f32 {
+    entries.iter().map(|entry| hours(entry.elapsed_in(range))).sum()
 }
 
 pub mod request {
     use super::*;
+    use std::collections::HashMap;
+    use std::future::Future;
+    use std::sync::{Mutex, OnceLock, PoisonError};
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
     use crate::config::Cfg;
-    use reqwest::blocking::Client;
-    use reqwest::Method;
+    use crate::error::Error;
+    use futures::{stream, Stream, StreamExt, TryStreamExt};
+    use reqwest::{Client, Method, Response, StatusCode};
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
     use serde_json::{to_string, from_str};
+    use tokio::runtime::{Builder, Runtime};
+    use tokio::time::sleep;
+
+    // ClickUp resets its per-token budget every minute, never wait longer than that for it
+    const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(65);
+    // ClickUp doesn't page time entries, so long ranges are fetched in windows of this size instead
+    const TIME_ENTRY_WINDOW_MS: i64 = 30 * 24 * 60 * 60 * 1000;
+    // ClickUp returns comments in pages of this size, newest first
+    const COMMENT_PAGE_SIZE: usize = 25;
+
+    static CLIENTS: OnceLock<Mutex<HashMap<(u64, u64), Client>>> = OnceLock::new();
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+    // one client per timeout settings so connections and TLS sessions are reused across requests,
+    // while a daemon reloading the cfg or a project overriding the timeouts still gets its own
+    fn client(cfg: &Cfg) -> Client {
+        let mut clients = CLIENTS.get_or_init(Default::default).lock().unwrap_or_else(PoisonError::into_inner);
+        clients
+            .entry((cfg.timeout_secs, cfg.connect_timeout_secs))
+            .or_insert_with(|| {
+                Client::builder()
+                    .timeout(Duration::from_secs(cfg.timeout_secs))
+                    .connect_timeout(Duration::from_secs(cfg.connect_timeout_secs))
+                    .build()
+                    .expect("Could not build the HTTP client")
+            })
+            .clone()
+    }
+
+    /// Runs a future of the async request layer to completion on the shared runtime.
+    /// Must not be called from inside async code.
+    pub fn block_on<F: Future>(future: F) -> F::Output {
+        RUNTIME
+            .get_or_init(|| {
+                Builder::new_multi_thread()
+                    .worker_threads(2)
+                    .enable_all()
+                    .build()
+                    .expect("Could not start the async runtime")
+            })
+            .block_on(future)
+    }
+
+    /// Runs the futures with at most `limit` of them in flight, returning the results in order
+    /// or the first error.
+    pub async fn bounded<T, E, F>(futures: impl IntoIterator<Item = F>, limit: usize) -> Result<Vec<T>, E>
+    where
+        F: Future<Output = Result<T, E>>,
+    {
+        stream::iter(futures).buffered(limit.max(1)).try_collect().await
+    }
+
+    fn is_idempotent(method: &Method) -> bool {
+        matches!(*method, Method::GET | Method::HEAD | Method::PUT | Method::DELETE)
+    }
+
+    fn header_u64(res: &Response, name: &str) -> Option<u64> {
+        res.headers().get(name)?.to_str().ok()?.parse::<u64>().ok()
+    }
+
+    fn backoff(cfg: &Cfg, attempt: u32) -> Duration {
+        Duration::from_millis(cfg.retry_base_ms.saturating_mul(1 << (attempt - 1).min(16)))
+    }
 
-    pub fn make_get_request(cfg: &Cfg, start: i64, end: i64, url: String) -> Result<TimeEntries, reqwest::Error> { // building request
-        let client = Client::new();
-        let req = client
-            .request(Method::GET, url)
-            .header("content-type", "appication/json")
-            .header("Authorization", cfg.token.clone());
-
-        // adding query params to request
-        let mut query_params: Vec<(String, String)> = Vec::new();
-        query_params.push(("start_date".to_string(), format!("{}", start)));
-        query_params.push(("end_date".to_string(), format!("{}", end)));
-        let res = req.query(&query_params).send()?.text()?;
-        let time_entries: TimeEntries = from_str(&res).unwrap();
-        Ok(time_entries)
-    }
-
-    pub fn make_post_request(cfg: &Cfg, url: String, body: HashMap<String, String>) -> Result<(), Box<dyn std::error::Error>> {
-        let client: Client = Client::new();
-        let req_body = to_string(&body)?;
-        let req = client.request(Method::POST , url).header("content-type", "application/json").header("Authorization", cfg.token.clone()).body(req_body.clone());
-
-        let status = req.send()?.status();
-        let success = status.is_success();
-
-        if success {
-            Ok(())
-        } else {
-            Err(format!("Request failed with status code: {}", status.as_u16()).into())
+    // waits until the rate limit resets if ClickUp told us when, otherwise falls back to the backoff
+    fn rate_limit_delay(cfg: &Cfg, attempt: u32, res: &Response) -> Duration {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        match header_u64(res, "X-RateLimit-Reset") {
+            Some(reset) if reset > now => Duration::from_secs(reset - now + 1).min(MAX_RATE_LIMIT_WAIT),
+            _ => backoff(cfg, attempt),
         }
     }
-}
 
-pub mod display {
-    use chrono::DateTime;
-    use chrono::Utc;
+    fn log(cfg: &Cfg, method: &Method, url: &str, started: Instant, outcome: String) {
+        if cfg.debug {
+            eprintln!("[DEBUG] {} {} -> {} in {}ms", method, url, outcome, started.elapsed().as_millis());
+        }
+    }
+
+    /// Sends a request to ClickUp, retrying 429s always and 5xx / timeouts only for idempotent methods,
+    /// and returns the response body.
+    pub async fn send(cfg: &Cfg, method: Method, url: &str, query_params: &[(&str, String)], body: Option<String>) -> Result<String, Error> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut req = client(cfg)
+                .request(method.clone(), url)
+                .header("content-type", "application/json")
+                .header("Authorization", cfg.token.clone())
+                .query(query_params);
+            if let Some(body) = &body {
+                req = req.body(body.clone());
+            }
+            let started = Instant::now();
+            let can_retry = attempt <= cfg.max_retries;
+            match req.send().await {
+                Ok(res) => {
+                    let status = res.status();
+                    let budget = match (header_u64(&res, "X-RateLimit-Remaining"), header_u64(&res, "X-RateLimit-Limit")) {
+                        (Some(remaining), Some(limit)) => format!(" (rate limit: {}/{} remaining)", remaining, limit),
+                        (Some(remaining), None) => format!(" (rate limit: {} remaining)", remaining),
+                        _ => String::new(),
+                    };
+                    log(cfg, &method, url, started, format!("{}{}", status.as_u16(), budget));
+                    let delay = if status == StatusCode::TOO_MANY_REQUESTS {
+                        rate_limit_delay(cfg, attempt, &res)
+                    } else if status.is_server_error() && is_idempotent(&method) {
+                        backoff(cfg, attempt)
+                    } else {
+                        return Ok(res.error_for_status()?.text().await?);
+                    };
+                    if !can_retry {
+                        return Ok(res.error_for_status()?.text().await?);
+                    }
+                    log(cfg, &method, url, started, format!("retrying in {}ms", delay.as_millis()));
+                    sleep(delay).await;
+                }
+                Err(e) => {
+                    log(cfg, &method, url, started, format!("error: {}", e));
+                    // a failed connect never reached the server, so even POSTs are safe to resend
+                    let retryable = e.is_connect() || (e.is_timeout() && is_idempotent(&method));
+                    if !retryable || !can_retry {
+                        return Err(e.into());
+                    }
+                    sleep(backoff(cfg, attempt)).await;
+                }
+            }
+        }
+    }
+
+    // generic GET for endpoints that are not time entries
+    pub async fn get_json<T: DeserializeOwned>(cfg: &Cfg, url: &str, query_params: &[(&str, String)]) -> Result<T, Error> {
+        let res = send(cfg, Method::GET, url, query_params, None).await?;
+        Ok(from_str(&res)?)
+    }
+
+    pub async fn post_json<T: DeserializeOwned, B: Serialize>(cfg: &Cfg, url: &str, body: &B) -> Result<T, Error> {
+        let res = send(cfg, Method::POST, url, &[], Some(to_string(body)?)).await?;
+        Ok(from_str(&res)?)
+    }
+
+    async fn get_time_entry_window(cfg: &Cfg, start: i64, end: i64, url: &str) -> Result<TimeEntries, Error> {
+        // ClickUp's end_date is inclusive, our windows are half-open
+        let query_params = [("start_date", format!("{}", start)), ("end_date", format!("{}", end - 1))];
+        get_json(cfg, url, &query_params).await
+    }
+
+    /// Time entries starting in `[start, end)` (milliseconds), fetched as concurrent windows.
+    pub async fn get_time_entries(cfg: &Cfg, start: i64, end: i64, url: &str) -> Result<TimeEntries, Error> {
+        let mut windows = Vec::new();
+        let mut window_start = start;
+        loop {
+            let window_end = end.min(window_start + TIME_ENTRY_WINDOW_MS);
+            windows.push((window_start, window_end));
+            if window_end >= end {
+                break;
+            }
+            window_start = window_end;
+        }
+        let pages = bounded(windows.into_iter().map(|(s, e)| get_time_entry_window(cfg, s, e, url)), cfg.max_concurrency).await?;
+        let mut data: Vec<TimeEntry> = Vec::new();
+        for entry in pages.into_iter().flat_map(|page| page.data) {
+            // entries crossing a window boundary are returned by both windows
+            if !data.iter().any(|e| e.id == entry.id) {
+                data.push(entry);
+            }
+        }
+        Ok(TimeEntries { data })
+    }
+
+    // a response of a paged endpoint that knows how to ask for the page after it
+    pub trait Paged: DeserializeOwned {
+        type Item;
+        // query params selecting the next page, None if this was the last one
+        fn next_query(&self, query: &[(&'static str, String)]) -> Option<Vec<(&'static str, String)>>;
+        fn into_items(self) -> Vec<Self::Item>;
+    }
 
-    use super::TimeEntry;
+    fn with_param(query: &[(&'static str, String)], key: &'static str, value: String) -> Vec<(&'static str, String)> {
+        let mut query: Vec<(&'static str, String)> = query.iter().filter(|(k, _)| *k != key).cloned().collect();
+        query.push((key, value));
+        query
+    }
 
-    pub const HOURGLASS: char = '\u{231B}';
-    pub const ERROR: char = '\u{1F6AB}';
-    const ALARM_CLOCK: char = '\u{23F0}';
-    const CHECKMARK: char = '\u{2705}';
-    const LABEL: char = '\u{1F4CA}';
+    impl Paged for Tasks {
+        type Item = Task;
 
+        fn next_query(&self, query: &[(&'static str, String)]) -> Option<Vec<(&'static str, String)>> {
+            if self.tasks.is_empty() || self.last_page.unwrap_or(false) {
+                return None;
+            }
+            let page = query.iter().find(|(k, _)| *k == "page").and_then(|(_, v)| v.parse::<u32>().ok()).unwrap_or(0);
+            Some(with_param(query, "page", (page + 1).to_string()))
+        }
 
-    pub fn fmt_time(hours: f32) -> String {
-        if hours.fract() == 0.0 {
-            format!("{:.0}h", hours)
-        } else {
-            format!("{:.2}h", hours)
+        fn into_items(self) -> Vec<Task> {
+            self.tasks
         }
     }
-    pub fn fmt_task(entry: &TimeEntry) -> String {
-        let mut out = String::with_capacity(64);
-        let last_entry_ts_ms = entry.end.parse::<i64>().unwrap();
-        let last_entry_ts_s = last_entry_ts_ms / 1000;
-        let last_entry_ts_ns = (last_entry_ts_ms % 1000) * 1_000_000;
-        let last_entry_dt = DateTime::from_timestamp(last_entry_ts_s, last_entry_ts_ns as u32).unwrap();
-
-        let last_tracked_in_mins = Utc::now().signed_duration_since(last_entry_dt).num_minutes();
-
-        if let Some(task) = &entry.task {
-            out.push_str(&format!("{: <14}", &format!("{} [TASK]", CHECKMARK)));
-            out.push_str(&format!(" {} ({})\n", task.name, entry.task_url.as_ref().unwrap()));
-            out.push_str(&format!("{: <14}", &format!("{} [STATUS]", LABEL)));
-            out.push_str(&format!(" {}\n", task.status.status));
-        } else {
-            out.push_str("No task associated with this entry\n");
+
+    impl Paged for Comments {
+        type Item = Comment;
+
+        // comments are paged by a cursor: the date and id of the oldest comment seen so far
+        fn next_query(&self, query: &[(&'static str, String)]) -> Option<Vec<(&'static str, String)>> {
+            if self.comments.len() < COMMENT_PAGE_SIZE {
+                return None;
+            }
+            let oldest = self.comments.last()?;
+            let start = oldest.date.timestamp_millis().to_string();
+            Some(with_param(&with_param(query, "start", start), "start_id", oldest.id.clone()))
+        }
+
+        fn into_items(self) -> Vec<Comment> {
+            self.comments
         }
+    }
+
+    /// The items of a paged endpoint page by page; a page is only requested once the one before
+    /// it was consumed, so dropping the stream stops paging.
+    pub fn pages<'a, P: Paged + 'a>(cfg: &'a Cfg, url: String, query: Vec<(&'static str, String)>) -> impl Stream<Item = Result<Vec<P::Item>, Error>> + 'a {
+        stream::try_unfold(Some(query), move |query| {
+            let url = url.clone();
+            async move {
+                let Some(current) = query else {
+                    return Ok(None);
+                };
+                let page: P = get_json(cfg, &url, &current).await?;
+                let next = page.next_query(&current);
+                Ok(Some((page.into_items(), next)))
+            }
+        })
+    }
 
-        out.push_str(&format!("{: <14}", &format!("{} [LAST ENTRY]", ALARM_CLOCK)));
-        out.push_str(&format!(" {} minutes ({}) ago\n", last_tracked_in_mins, fmt_time(last_tracked_in_mins as f32 / 60f32)));
-        out.push_str(&format!("{: <14}", &format!("{} [DURATION]", HOURGLASS)));
-        out.push_str(&format!(" {} minutes ({})\n", entry.duration.parse::<f32>().unwrap() / 1000f32 / 60f32, fmt_time(entry.duration.parse::<f32>().unwrap() / 1000f32 / 60f32 / 60f32)));
-        out
+    /// Runs a stream on the shared runtime as a blocking iterator.
+    pub fn blocking<'a, T: 'a>(stream: impl Stream<Item = T> + 'a) -> impl Iterator<Item = T> + 'a {
+        let mut stream = Box::pin(stream);
+        std::iter::from_fn(move || block_on(stream.next()))
     }
 }